@@ -5,10 +5,11 @@
 //! ## About
 
 //! `vapor_archive` is, for the most part, a clone of the ZIP file format, with a few differences:
-//! * File headers are stored after the data (making them footers),  
-//! but the file format doesnt care about this
+//! * File headers are stored in front of the data, framed so archives can be read front to back  
+//!   from sources that can't seek
 //! * Uses BLAKE2S instead of CRC32 for the checksums
 //! * Supports ZStandard compression
+//! * Supports AES-256 encryption
 
 //! ## Why?
 
@@ -35,18 +36,23 @@ pub mod write;
 pub mod shared;
 
 pub use read::archive::Archive as VarArchive;
+pub use read::shared_archive::SharedArchive as VarSharedArchive;
+pub use read::mapped_archive::MappedArchive as VarMappedArchive;
+pub use read::slice_archive::SliceArchive as VarSliceArchive;
 pub use read::file::File as VarFile;
+pub use read::stream_reader::StreamReader as VarStreamReader;
 pub use write::writer::Writer as VarWriter;
 pub use shared::compression::Compression;
+pub use write::file_options::FileOptions as VarFileOptions;
 
 /// Full version
-pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Major version
-pub const VERSION_MAJOR: &'static str = env!("CARGO_PKG_VERSION_MAJOR");
+pub const VERSION_MAJOR: &str = env!("CARGO_PKG_VERSION_MAJOR");
 /// Minor version
-pub const VERSION_MINOR: &'static str = env!("CARGO_PKG_VERSION_MINOR");
+pub const VERSION_MINOR: &str = env!("CARGO_PKG_VERSION_MINOR");
 /// Patch version
-pub const VERSION_PATCH: &'static str = env!("CARGO_PKG_VERSION_PATCH");
+pub const VERSION_PATCH: &str = env!("CARGO_PKG_VERSION_PATCH");
 
 /// Gets the library version
 /// 
@@ -69,8 +75,6 @@ pub fn get_version_bytes() -> [u8; 16] {
     if version_bytes.len() > ret.len() {
         panic!("ERROR! Library version would not fit into header!");
     }
-    for i in 0..version_bytes.len() {
-        ret[i] = version_bytes[i];
-    }
+    ret[..version_bytes.len()].copy_from_slice(version_bytes);
     ret
 }
\ No newline at end of file
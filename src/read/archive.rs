@@ -4,23 +4,66 @@ use crate::{
         archive_header::ArchiveHeader,
         file_header::FileHeader,
         file_info::FileInfo,
+        encryption::{
+            Encryption,
+            Key
+        },
+        key_derivation::KeyDerivation,
+        trailer::{
+            Trailer,
+            TRAILER_SIZE
+        },
+        encoding::Decode,
+        format_version::{
+            FormatVersion,
+            FORMAT_VERSION_SIZE
+        },
         error::{
             Error,
             Result
         }
     },
     read::{
-        file::File
+        file::File,
+        versioned,
+        frame_reader::FrameReader,
+        crypt_reader::{
+            self,
+            CryptReader
+        },
+        verify::{
+            VerifyReport,
+            FailureKind
+        }
     }
 };
 
 use std::{
     io::{
         Read,
+        Write,
         Seek,
-        SeekFrom
+        SeekFrom,
+        Take,
+        Cursor,
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
     },
-    ops::Range
+    borrow::Cow,
+    convert::TryFrom,
+    ops::Range,
+    sync::Arc
+};
+
+use blake2::{
+    Blake2s,
+    Digest
+};
+
+use tracing::{
+    debug,
+    debug_span
 };
 
 
@@ -28,22 +71,84 @@ use std::{
 pub struct Archive<R: Read + Seek> {
     /// The source - e.g. a file
     source: R,
-    /// The directory
-    directory: Directory,
+    /// The archive header
+    header: ArchiveHeader,
+    /// The directory, shared by all handles to the same archive
+    directory: Arc<Directory>,
+    /// Byte range of the directory
+    directory_range: Range<u64>,
+    /// The on-disk format version
+    format_version: FormatVersion,
+    /// Byte offset of the first entry, right after the archive header
+    entries_begin: u64,
+    /// Optional key for encrypted files
+    key_opt: Option<Key>,
+    /// Whether or not to verify the data checksum before decompressing
+    verify_data: bool
 }
 
 impl<'r, R: Read + Seek + 'r> Archive<R> {
     /// Creates a new archive, wrapping the given source
-    pub fn new(mut source: R) -> Result<Self> {
+    /// 
+    /// Fails with `Error::KeyRequired` if the directory is encrypted.
+    pub fn new(source: R) -> Result<Self> {
+        Self::open(source, None, None)
+    }
+
+    /// Creates a new archive with a given encryption key, wrapping the given source
+    pub fn new_with_key(source: R, key: Key) -> Result<Self> {
+        Self::open(source, Some(key), None)
+    }
+
+    /// Creates a new archive from a password protected source
+    /// 
+    /// The key is derived from the password using the parameters stored in the archive header.
+    pub fn new_with_password(source: R, password: &str) -> Result<Self> {
+        Self::open(source, None, Some(password))
+    }
+
+    /// Opens the archive, using either the given key or one derived from the given password
+    fn open(mut source: R, mut key_opt: Option<Key>, password_opt: Option<&str>) -> Result<Self> {
+        let _span = debug_span!("open_archive").entered();
         // Read the magic bytes
         let mut magic_bytes = [0u8; 3];
         source.read_exact(&mut magic_bytes)
-            .map_err(|_| Error::CantReadMagicBytes)?;
+            .map_err(Error::CantReadMagicBytes)?;
         if &magic_bytes != b"VAR" {
             return Err(Error::IncorrectMagicBytes(magic_bytes));
         }
-        let header: ArchiveHeader = bincode::deserialize_from(&mut source)
-            .map_err(|_| Error::CorruptHeader)?;
+        // Check the format version before touching anything that depends on it
+        let mut format_version_bytes = [0u8; FORMAT_VERSION_SIZE];
+        source.read_exact(&mut format_version_bytes)
+            .map_err(Error::CantReadHeader)?;
+        let format_version = if versioned::is_revision_0(&format_version_bytes) {
+            // Revision 0 stored the header right after the magic bytes
+            source.seek(SeekFrom::Start(3))
+                .map_err(Error::CantReadHeader)?;
+            FormatVersion::REVISION_0
+        } else {
+            FormatVersion::from_bytes(format_version_bytes)
+        };
+        if !format_version.is_supported() {
+            return Err(Error::VersionMismatch {
+                found: format_version,
+                supported: FormatVersion::CURRENT
+            });
+        }
+        let (header, directory_range_opt) = versioned::decode_archive_header(format_version, &mut source)
+            .map_err(Error::CorruptHeader)?;
+        let entries_begin = source.stream_position()
+            .map_err(Error::CantReadHeader)?;
+        let directory_range = match directory_range_opt {
+            Some(directory_range) => directory_range,
+            None => read_trailer(&mut source)?.directory_range
+        };
+        if let Some(password) = password_opt {
+            let key = header.key_derivation.as_ref()
+                .ok_or(Error::NotPasswordProtected)?
+                .derive_key(password)?;
+            key_opt = Some(key);
+        }
         // // Prepare the buffer
         // let mut directory_bytes: Vec<u8> = vec![];
         // let directory_len = (header.directory.end - header.directory.start) as usize;
@@ -55,19 +160,88 @@ impl<'r, R: Read + Seek + 'r> Archive<R> {
         // source.read_exact(&mut directory_bytes)
         //     .map_err(|_| Error::CantReadDirectory)?;
         // // Parse the directory
-        let dir_begin = header.directory_range.start;
+        let dir_begin = directory_range.start;
         source.seek(SeekFrom::Start(dir_begin))
-            .map_err(|_| Error::CantReadDirectory)?;
-        let directory: Directory = bincode::deserialize_from(&mut source)
-            .map_err(|_| Error::CorruptDirectory)?;
+            .map_err(|source| Error::CantReadDirectory { offset: dir_begin, source })?;
+        let directory: Directory = match header.index_encryption {
+            Encryption::None => versioned::decode_directory(format_version, &mut source)
+                .map_err(|source| Error::CorruptDirectory { offset: dir_begin, source })?,
+            _ => {
+                let key = key_opt.as_ref().ok_or(Error::KeyRequired)?;
+                let directory_size = directory_range.end.saturating_sub(dir_begin);
+                let directory_bytes = crypt_reader::read_sealed(&mut source, directory_size, key)
+                    .map_err(|error| error.map_io(|source| Error::CantReadDirectory { offset: dir_begin, source }))?;
+                versioned::decode_directory(format_version, &directory_bytes[..])
+                    .map_err(|source| Error::CorruptDirectory { offset: dir_begin, source })?
+            }
+        };
+        debug!(
+            entries = directory.file_headers.len(),
+            directory_begin = dir_begin,
+            index_encrypted = header.index_encryption != Encryption::None,
+            format_version = %format_version,
+            "Opened archive"
+        );
         Ok(
             Self {
-                directory: directory,
-                source: source
+                header,
+                directory: Arc::new(directory),
+                directory_range,
+                format_version,
+                entries_begin,
+                source,
+                key_opt,
+                verify_data: false
             }
         )
     }
 
+    /// Creates another handle to the same archive, reading through the given source
+    /// 
+    /// The source has to hold the same archive. Nothing gets read, the directory is shared.
+    pub(crate) fn with_source<S: Read + Seek>(&self, source: S) -> Archive<S> {
+        Archive {
+            source,
+            header: self.header.clone(),
+            directory: self.directory.clone(),
+            directory_range: self.directory_range.clone(),
+            format_version: self.format_version,
+            entries_begin: self.entries_begin,
+            key_opt: self.key_opt,
+            verify_data: self.verify_data
+        }
+    }
+
+    /// Gets the underlying source
+    pub(crate) fn get_source(&self) -> &R {
+        &self.source
+    }
+
+    /// Returns a boolean indicating whether or not the directory and file headers are encrypted
+    pub fn is_index_encrypted(&self) -> bool {
+        self.header.index_encryption != Encryption::None
+    }
+
+    /// Gets the on-disk format version of the archive
+    pub fn get_format_version(&self) -> FormatVersion {
+        self.format_version
+    }
+
+    /// Gets the key derivation parameters, if the archive is password protected
+    pub fn get_key_derivation(&self) -> Option<&KeyDerivation> {
+        self.header.key_derivation.as_ref()
+    }
+
+    /// ...with data checksum verification enabled or disabled
+    /// 
+    /// When enabled, the checksum of an entries compressed/encrypted data is
+    /// verified before any of it gets decompressed. This requires an extra pass
+    /// over the data, which is why it is disabled by default.
+    pub fn with_data_verification(mut self, verify_data: bool) -> Self {
+        self.verify_data = verify_data;
+        self
+    }
+
     /// Lists all files
     pub fn get_file_list(&self) -> Vec<String> {
         self.directory.get_file_list()
@@ -77,15 +251,52 @@ impl<'r, R: Read + Seek + 'r> Archive<R> {
     pub fn get_file(&'r mut self, path: &str) -> Result<File<'r, R>> {
         let file_header_range = self.directory.get_file(path)
             .ok_or(Error::FileNotFound(String::from(path)))?;
-        let file_header = self.get_file_header(file_header_range)?;
-        File::new(&mut self.source, file_header.file_info.raw_size, file_header.data_range.clone(), file_header.file_info.compression.clone())
+        let file_header = self.get_file_header(path, file_header_range)?;
+        let file_info = file_header.file_info
+            .with_filename(path);
+        File::new(&mut self.source, file_header.data_range, file_info, self.key_opt.as_ref(), self.verify_data)
+    }
+
+    /// Reads a byte range of a file's uncompressed/decrypted data
+    /// 
+    /// Uncompressed files are read directly, files compressed in independent frames only
+    /// decompress the frames covering the range. Encrypted files only decrypt the chunks covering it.
+    /// Any other compressed file gets decompressed from the start up to the end of the range.
+    /// The range has to lie within the file.
+    pub fn read_range(&mut self, path: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        let file_header_range = self.directory.get_file(path)
+            .ok_or(Error::FileNotFound(String::from(path)))?;
+        let file_header = self.get_file_header(path, file_header_range)?;
+        let raw_size = file_header.file_info.raw_size;
+        if range.start > range.end || range.end > raw_size {
+            return Err(Error::InvalidRange {
+                path: String::from(path),
+                range,
+                raw_size
+            });
+        }
+        let data_begin = file_header.data_range.start;
+        let file_info = file_header.file_info
+            .with_filename(path);
+        let mut file = File::new(&mut self.source, file_header.data_range, file_info, self.key_opt.as_ref(), self.verify_data)?;
+        let read_error = |source| Error::CantReadFile {
+            path: String::from(path),
+            offset: data_begin,
+            source
+        };
+        file.seek(SeekFrom::Start(range.start))
+            .map_err(read_error)?;
+        let mut bytes = vec![0u8; (range.end - range.start) as usize];
+        file.read_exact(&mut bytes)
+            .map_err(read_error)?;
+        Ok(bytes)
     }
 
     /// Gets a files info by path
     pub fn get_file_info(&mut self, path: &str) -> Result<FileInfo> {
         let file_header_range = self.directory.get_file(path)
             .ok_or(Error::FileNotFound(String::from(path)))?;
-        let file_header = self.get_file_header(file_header_range)?;
+        let file_header = self.get_file_header(path, file_header_range)?;
         let file_info = file_header.file_info.clone()
             .with_filename(path);
         Ok(
@@ -93,18 +304,269 @@ impl<'r, R: Read + Seek + 'r> Archive<R> {
         )
     }
 
+    /// Gets a files info along with its stored, still compressed/encrypted data
+    pub(crate) fn get_stored_data(&mut self, path: &str) -> Result<(FileInfo, Take<&mut R>)> {
+        let file_header_range = self.directory.get_file(path)
+            .ok_or(Error::FileNotFound(String::from(path)))?;
+        let file_header = self.get_file_header(path, file_header_range)?;
+        let data_range = file_header.data_range;
+        self.source.seek(SeekFrom::Start(data_range.start))
+            .map_err(|source| Error::CantReadFile {
+                path: String::from(path),
+                offset: data_range.start,
+                source
+            })?;
+        let data = (&mut self.source).take(data_range.end.saturating_sub(data_range.start));
+        Ok((file_header.file_info, data))
+    }
+
+    /// Gets a files stored data, borrowed from the given bytes of the whole archive
+    /// 
+    /// Only files that are neither compressed nor encrypted are stored as-is, any other fails with `Error::NotStored`.
+    pub(crate) fn get_stored_slice<'b>(&mut self, bytes: &'b [u8], path: &str) -> Result<&'b [u8]> {
+        let file_header_range = self.directory.get_file(path)
+            .ok_or(Error::FileNotFound(String::from(path)))?;
+        let file_header = self.get_file_header(path, file_header_range)?;
+        let file_info = &file_header.file_info;
+        if file_info.is_compressed() || file_info.is_encrypted() {
+            return Err(Error::NotStored(String::from(path)));
+        }
+        self.slice_data(bytes, path, &file_header)
+    }
+
+    /// Gets a files uncompressed/decrypted data out of the given bytes of the whole archive
+    /// 
+    /// Files stored as-is are borrowed, any other file gets decompressed/decrypted into a new buffer.
+    pub(crate) fn get_data<'b>(&mut self, bytes: &'b [u8], path: &str) -> Result<Cow<'b, [u8]>> {
+        let file_header_range = self.directory.get_file(path)
+            .ok_or(Error::FileNotFound(String::from(path)))?;
+        let file_header = self.get_file_header(path, file_header_range)?;
+        if !file_header.file_info.is_compressed() && !file_header.file_info.is_encrypted() {
+            return self.slice_data(bytes, path, &file_header)
+                .map(Cow::Borrowed);
+        }
+        let data_begin = file_header.data_range.start;
+        let file_info = file_header.file_info
+            .with_filename(path);
+        let mut source = Cursor::new(bytes);
+        let mut file = File::new(&mut source, file_header.data_range, file_info, self.key_opt.as_ref(), self.verify_data)?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)
+            .map_err(|source| Error::CantReadFile {
+                path: String::from(path),
+                offset: data_begin,
+                source
+            })?;
+        Ok(Cow::Owned(data))
+    }
+
+    /// Slices a files stored data out of the given bytes of the whole archive
+    fn slice_data<'b>(&self, bytes: &'b [u8], path: &str, file_header: &FileHeader) -> Result<&'b [u8]> {
+        let data_range = &file_header.data_range;
+        let data = usize::try_from(data_range.start).ok()
+            .zip(usize::try_from(data_range.end).ok())
+            .and_then(|(start, end)| bytes.get(start..end))
+            .ok_or_else(|| Error::CantReadFile {
+                path: String::from(path),
+                offset: data_range.start,
+                source: IoError::from(ErrorKind::UnexpectedEof)
+            })?;
+        if self.verify_data {
+            let data_checksum: [u8; 32] = Blake2s::digest(data).into();
+            if data_checksum != file_header.file_info.data_checksum {
+                return Err(Error::DataChecksumMismatch(String::from(path)));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Gets the byte offset of a files header, if the file exists
+    pub(crate) fn get_file_offset(&self, path: &str) -> Option<u64> {
+        self.directory.get_file(path)
+            .map(|range| range.start)
+    }
+
+    /// Gets the key used for decrypting files, if any
+    pub(crate) fn get_key(&self) -> Option<&Key> {
+        self.key_opt.as_ref()
+    }
+
+    /// Verifies the integrity of every entry in the archive
+    /// 
+    /// Re-reads every file header and checks the header and data ranges, sizes
+    /// and both checksums. Problems with individual entries are collected in the
+    /// returned report; an `Err` is only returned if the archive itself couldnt be read.
+    pub fn verify(&mut self) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        // Entries have to lie between the archive header and the directory
+        let entries_end = self.source.seek(SeekFrom::End(0))?
+            .min(self.directory_range.start);
+        let area = self.entries_begin..entries_end;
+        let mut entries: Vec<(String, Range<u64>)> = self.directory.file_headers.iter()
+            .map(|(path, range)| (path.clone(), range.clone()))
+            .collect();
+        entries.sort_by_key(|(_, range)| range.start);
+        // Every range that is occupied, along with the entry it belongs to
+        let mut occupied: Vec<(Range<u64>, String)> = vec![];
+        for (path, header_range) in entries.iter() {
+            report.entries_checked += 1;
+            if !contains_range(&area, header_range) {
+                report.fail(path, FailureKind::HeaderRangeOutOfBounds(header_range.clone()));
+                continue;
+            }
+            occupied.push((header_range.clone(), path.clone()));
+            let file_header = match self.get_file_header(path, header_range.clone()) {
+                Ok(file_header) => file_header,
+                Err(_) => {
+                    report.fail(path, FailureKind::HeaderDecode);
+                    continue;
+                }
+            };
+            let data_range = file_header.data_range.clone();
+            if !contains_range(&area, &data_range) {
+                report.fail(path, FailureKind::DataRangeOutOfBounds(data_range));
+                continue;
+            }
+            occupied.push((data_range.clone(), path.clone()));
+            let data_size = data_range.end - data_range.start;
+            let file_info = &file_header.file_info;
+            if data_size != file_info.data_size {
+                report.fail(path, FailureKind::DataSizeMismatch {
+                    expected: file_info.data_size,
+                    actual: data_size
+                });
+            }
+            // Checksum of the compressed/encrypted data
+            let offset = data_range.start;
+            let read_error = |source| Error::CantReadFile { path: path.clone(), offset, source };
+            self.source.seek(SeekFrom::Start(offset))
+                .map_err(read_error)?;
+            let mut hasher = Blake2s::new();
+            std::io::copy(&mut (&mut self.source).take(data_size), &mut HashSink(&mut hasher))
+                .map_err(read_error)?;
+            let data_checksum: [u8; 32] = hasher.result().into();
+            if data_checksum != file_info.data_checksum {
+                report.fail(path, FailureKind::DataChecksumMismatch);
+            }
+            // Checksum of the uncompressed/decrypted data
+            self.source.seek(SeekFrom::Start(offset))
+                .map_err(read_error)?;
+            let data = (&mut self.source).take(data_size);
+            let crypt_reader = match CryptReader::new(data, file_info.encryption.clone(), self.key_opt.as_ref()) {
+                Ok(crypt_reader) => crypt_reader,
+                Err(_) => {
+                    report.fail(path, FailureKind::Decryption);
+                    continue;
+                }
+            };
+            let mut frame_reader = match FrameReader::new(crypt_reader, file_info, offset..offset + data_size) {
+                Ok(frame_reader) => frame_reader,
+                Err(_) => {
+                    report.fail(path, FailureKind::Decompression);
+                    continue;
+                }
+            };
+            let mut hasher = Blake2s::new();
+            let mut buffer = [0u8; 8192];
+            let mut raw_size = 0u64;
+            let mut decompressed = true;
+            while raw_size < file_info.raw_size {
+                let chunk_size = (file_info.raw_size - raw_size).min(buffer.len() as u64) as usize;
+                match frame_reader.read(&mut buffer[0..chunk_size]) {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        hasher.input(&buffer[0..read]);
+                        raw_size += read as u64;
+                    },
+                    Err(_) => {
+                        decompressed = false;
+                        break;
+                    }
+                }
+            }
+            if !decompressed {
+                report.fail(path, FailureKind::Decompression);
+            } else if raw_size != file_info.raw_size {
+                report.fail(path, FailureKind::RawSizeMismatch {
+                    expected: file_info.raw_size,
+                    actual: raw_size
+                });
+            } else {
+                let raw_checksum: [u8; 32] = hasher.result().into();
+                if raw_checksum != file_info.raw_checksum {
+                    report.fail(path, FailureKind::RawChecksumMismatch);
+                }
+            }
+        }
+        // Look for overlapping ranges, both within and across entries. Comparing against the range
+        // reaching furthest so far also catches ranges nested in or spanning several others
+        occupied.sort_by_key(|(range, _)| range.start);
+        let mut furthest_opt: Option<(u64, &String)> = None;
+        for (range, path) in occupied.iter() {
+            if let Some((end, furthest_path)) = furthest_opt {
+                if range.start < end {
+                    report.fail(path, FailureKind::OverlappingRanges(furthest_path.clone()));
+                }
+            }
+            if !matches!(furthest_opt, Some((end, _)) if end >= range.end) {
+                furthest_opt = Some((range.end, path));
+            }
+        }
+        Ok(report)
+    }
+
     /// Gets a file header
-    fn get_file_header(&mut self, file_header_range: Range<u64>) -> Result<FileHeader> {
+    fn get_file_header(&mut self, path: &str, file_header_range: Range<u64>) -> Result<FileHeader> {
         let file_header_start = file_header_range.start;
         let file_header_range = file_header_range.start as usize .. file_header_range.end as usize;
         let file_header_size = file_header_range.end - file_header_range.start;
-        let mut file_header_bytes = vec![];
-        file_header_bytes.resize(file_header_size, 0);
+        let mut file_header_bytes = vec![0; file_header_size];
+        let read_error = |source| Error::CantReadFileHeader {
+            path: String::from(path),
+            offset: file_header_start,
+            source
+        };
         self.source.seek(SeekFrom::Start(file_header_start))
-            .map_err(|_| Error::CantReadFileHeader)?;
+            .map_err(read_error)?;
         self.source.read_exact(&mut file_header_bytes)
-            .map_err(|_| Error::CantReadFileHeader)?;
-        bincode::deserialize(&file_header_bytes)
-            .map_err(|_| Error::CorruptFileHeader)
+            .map_err(read_error)?;
+        if self.is_index_encrypted() {
+            let key = self.key_opt.as_ref().ok_or(Error::KeyRequired)?;
+            file_header_bytes = crypt_reader::read_sealed(&file_header_bytes[..], file_header_size as u64, key)
+                .map_err(|error| error.map_io(read_error))?;
+        }
+        versioned::decode_file_header(self.format_version, &file_header_bytes)
+            .map_err(|source| Error::CorruptFileHeader {
+                path: String::from(path),
+                offset: file_header_start,
+                source
+            })
+    }
+}
+
+/// Reads the trailer at the end of the archive
+fn read_trailer<R: Read + Seek>(source: &mut R) -> Result<Trailer> {
+    source.seek(SeekFrom::End(-(TRAILER_SIZE as i64)))
+        .map_err(Error::CantReadTrailer)?;
+    Trailer::decode(source)
+        .map_err(|error| Error::CorruptTrailer(error.into()))
+}
+
+/// Checks whether the inner range lies completely within the outer one
+fn contains_range(outer: &Range<u64>, inner: &Range<u64>) -> bool {
+    inner.start <= inner.end && inner.start >= outer.start && inner.end <= outer.end
+}
+
+/// Adapter for feeding a `Digest` through `std::io::copy`
+struct HashSink<'h>(&'h mut Blake2s);
+
+impl<'h> Write for HashSink<'h> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.0.input(buf);
+        Ok(buf.len())
     }
-}
\ No newline at end of file
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
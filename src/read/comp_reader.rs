@@ -1,6 +1,13 @@
 use crate::{
     shared::{
-        compression::Compression
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        error::{
+            Error,
+            Result
+        }
     }
 };
 
@@ -8,48 +15,109 @@ use std::{
     io::{
         Read,
         BufReader,
-        Seek,
+        Error as IoError,
+        ErrorKind,
         Result as IoResult
     }
 };
 
-use zstd::{
-    Decoder
+use zstd::stream::{
+    zio,
+    raw::{
+        self,
+        DParameter
+    }
+};
+
+use flate2::read::DeflateDecoder;
+
+use bzip2::read::BzDecoder;
+
+use xz2::{
+    read::XzDecoder,
+    stream::Stream
 };
 
 /// CompReader Enum
 /// 
 /// Wraps a reader in various supported compression algorithms.
-pub enum CompReader<'r, R: Read + Seek + 'r> {
+pub enum CompReader<R: Read> {
     /// None - misconfigured reader.
     None,
     /// Raw. No compression.
-    Raw(&'r mut R),
+    Raw(R),
     /// Zstd. Comparable to DEFLATE, but much faster.
-    ZStandard(Decoder<BufReader<&'r mut R>>),
+    ZStandard(zio::Reader<BufReader<R>, raw::Decoder>),
+    /// LZ4. Worse compression ratio than zstd, but even faster decompression.
+    LZ4(lz4::Decoder<R>),
+    /// LZMA, using the legacy .lzma container. Very high compression ratio, but slow.
+    LZMA(XzDecoder<R>),
+    /// LZMA2, using the .xz container.
+    LZMA2(XzDecoder<R>),
+    /// Raw deflate, as used within ZIP archives.
+    Deflate(DeflateDecoder<R>),
+    /// BZIP2.
+    BZIP2(BzDecoder<R>),
 }
 
-impl<'r, R: Read + Seek + 'r> CompReader<'r, R> {
-    /// Creates a new CompReader with a given type, wrapping a reader
-    pub fn new(reader: &'r mut R, compression_type: Compression) -> Self {
-        match compression_type {
+impl<R: Read> CompReader<R> {
+    /// Creates a new CompReader with a given type, wrapping a decrypting reader
+    /// 
+    /// The parameters are the ones the data was compressed with. As they are read from the archive,
+    /// ones the decoder rejects are invalid data.
+    pub fn new(reader: R, compression_type: Compression, params: &CompressionParams) -> Result<Self> {
+        let comp_reader = match compression_type {
             Compression::None => CompReader::Raw(reader),
             Compression::ZStandard => {
-                let decoder = Decoder::new(reader).expect("Error creating Zstd decoder!");
-                CompReader::ZStandard(decoder)
+                let mut decoder = raw::Decoder::new()?;
+                // Windows larger than the default limit have to be allowed explicitly
+                if let Some(window_log) = params.window_log {
+                    decoder.set_parameter(DParameter::WindowLogMax(window_log))
+                        .map_err(|source| IoError::new(ErrorKind::InvalidData, source))?;
+                }
+                CompReader::ZStandard(zio::Reader::new(BufReader::new(reader), decoder))
             },
-            _ => unimplemented!("Unimplemented compression type!")
+            Compression::LZ4 => CompReader::LZ4(lz4::Decoder::new(reader)?),
+            Compression::LZMA => {
+                let stream = Stream::new_lzma_decoder(u64::MAX)
+                    .map_err(IoError::from)?;
+                CompReader::LZMA(XzDecoder::new_stream(reader, stream))
+            },
+            Compression::LZMA2 => CompReader::LZMA2(XzDecoder::new(reader)),
+            Compression::Deflate => CompReader::Deflate(DeflateDecoder::new(reader)),
+            Compression::BZIP2 => CompReader::BZIP2(BzDecoder::new(reader)),
+            other => return Err(Error::UnsupportedCompression(other))
+        };
+        Ok(comp_reader)
+    }
+
+    /// Unwraps the underlying reader
+    /// 
+    /// Anything the decoder has buffered, but not yet decompressed, is lost.
+    pub fn into_inner(self) -> R {
+        match self {
+            CompReader::Raw(reader) => reader,
+            CompReader::ZStandard(decoder) => decoder.into_inner().into_inner(),
+            CompReader::LZ4(decoder) => decoder.finish().0,
+            CompReader::LZMA(decoder) | CompReader::LZMA2(decoder) => decoder.into_inner(),
+            CompReader::Deflate(decoder) => decoder.into_inner(),
+            CompReader::BZIP2(decoder) => decoder.into_inner(),
+            CompReader::None => panic!("Misconfigured reader!")
         }
     }
 }
 
-impl<'r, R: Read + Seek + 'r> Read for CompReader<'r, R> {
+impl<R: Read> Read for CompReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
         let reader_ref: &mut dyn Read = match self {
             CompReader::Raw(reader) => reader,
             CompReader::ZStandard(encoder) => encoder,
-            _ => panic!("Misconfigured reader!")
+            CompReader::LZ4(decoder) => decoder,
+            CompReader::LZMA(decoder) | CompReader::LZMA2(decoder) => decoder,
+            CompReader::Deflate(decoder) => decoder,
+            CompReader::BZIP2(decoder) => decoder,
+            CompReader::None => return Err(IoError::other("Reader misconfigured!"))
         };
         reader_ref.read(buf)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,307 @@
+use crate::{
+    shared::{
+        encryption::{
+            self,
+            Encryption,
+            Key,
+            AES_IV_SIZE,
+            KEY_CHECK_SIZE,
+            GCM_NONCE_PREFIX_SIZE,
+            GCM_TAG_SIZE,
+            GCM_MAX_CHUNK_SIZE
+        },
+        error::{
+            Error,
+            Result
+        }
+    }
+};
+
+use std::{
+    io::{
+        Read,
+        Seek,
+        SeekFrom,
+        Take,
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
+    },
+    ops::Range
+};
+
+use crypto::{
+    aead::AeadDecryptor,
+    aessafe::AesSafe256Encryptor,
+    blockmodes::CtrMode,
+    symmetriccipher::SynchronousStreamCipher
+};
+
+/// Byte size of an AES block, by which the CTR counter advances
+const AES_BLOCK_SIZE: u64 = 16;
+
+/// AesReader struct
+/// 
+/// Decrypts data that was encrypted using AES-256 in CTR mode.
+pub struct AesReader<R: Read> {
+    /// The underlying reader
+    reader: R,
+    /// The AES-CTR cipher
+    cipher: Box<CtrMode<AesSafe256Encryptor>>,
+    /// The encryption key
+    key: Key,
+    /// The IV the counter started at
+    iv: [u8; AES_IV_SIZE],
+    /// Buffer for the encrypted bytes
+    buffer: Vec<u8>
+}
+
+impl<R: Read> AesReader<R> {
+    /// Creates a new instance, wrapping the given reader
+    /// 
+    /// Reads the IV and the key check value, failing if the key doesnt match.
+    pub fn new(mut reader: R, key: &Key) -> Result<Self> {
+        let mut iv = [0u8; AES_IV_SIZE];
+        reader.read_exact(&mut iv)?;
+        let mut check = [0u8; KEY_CHECK_SIZE];
+        reader.read_exact(&mut check)?;
+        if encryption::key_check(key, &iv) != check {
+            return Err(Error::WrongKey);
+        }
+        Ok(
+            Self {
+                reader,
+                cipher: Box::new(encryption::aes_ctr(key, &iv)),
+                key: *key,
+                iv,
+                buffer: vec![]
+            }
+        )
+    }
+
+    /// Restarts the cipher at the given offset of the decrypted data
+    /// 
+    /// The underlying reader has to be positioned accordingly.
+    fn restart_at(&mut self, offset: u64) {
+        let block = u128::from_be_bytes(self.iv).wrapping_add((offset / AES_BLOCK_SIZE) as u128);
+        *self.cipher = encryption::aes_ctr(&self.key, &block.to_be_bytes());
+        let skip = [0u8; AES_BLOCK_SIZE as usize];
+        let mut skipped = [0u8; AES_BLOCK_SIZE as usize];
+        let skip_length = (offset % AES_BLOCK_SIZE) as usize;
+        self.cipher.process(&skip[0..skip_length], &mut skipped[0..skip_length]);
+    }
+}
+
+impl<R: Read> Read for AesReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        self.buffer.resize(buf.len(), 0);
+        let read = self.reader.read(&mut self.buffer)?;
+        self.cipher.process(&self.buffer[0..read], &mut buf[0..read]);
+        Ok(read)
+    }
+}
+
+/// GcmReader struct
+/// 
+/// Decrypts data that was encrypted using AES-256-GCM in chunks.
+/// Every chunk is authenticated before any of its bytes are returned.
+pub struct GcmReader<R: Read> {
+    /// The underlying reader, limited to the encrypted data
+    reader: Take<R>,
+    /// The encryption key
+    key: Key,
+    /// The per-file nonce prefix
+    nonce_prefix: [u8; GCM_NONCE_PREFIX_SIZE],
+    /// Plaintext size of each chunk
+    chunk_size: u32,
+    /// Index of the next chunk
+    chunk_index: u32,
+    /// Buffer for the encrypted chunk
+    in_buffer: Vec<u8>,
+    /// The decrypted chunk
+    buffer: Vec<u8>,
+    /// Read position within the decrypted chunk
+    position: usize,
+    /// Whether or not the last chunk has been read
+    finished: bool
+}
+
+impl<R: Read> GcmReader<R> {
+    /// Creates a new instance, wrapping the given reader
+    /// 
+    /// Reads the nonce prefix, key check value and chunk size, failing if the key doesnt match.
+    pub fn new(mut reader: Take<R>, key: &Key) -> Result<Self> {
+        let mut nonce_prefix = [0u8; GCM_NONCE_PREFIX_SIZE];
+        reader.read_exact(&mut nonce_prefix)?;
+        let mut check = [0u8; KEY_CHECK_SIZE];
+        reader.read_exact(&mut check)?;
+        if encryption::key_check(key, &nonce_prefix) != check {
+            return Err(Error::WrongKey);
+        }
+        let mut chunk_size_bytes = [0u8; 4];
+        reader.read_exact(&mut chunk_size_bytes)?;
+        let chunk_size = u32::from_le_bytes(chunk_size_bytes);
+        if chunk_size == 0 || chunk_size > GCM_MAX_CHUNK_SIZE {
+            return Err(Error::AuthenticationFailed);
+        }
+        Ok(
+            Self {
+                reader,
+                key: *key,
+                nonce_prefix,
+                chunk_size,
+                chunk_index: 0,
+                in_buffer: vec![],
+                buffer: vec![],
+                position: 0,
+                finished: false
+            }
+        )
+    }
+
+    /// Reads, authenticates and decrypts the next chunk
+    fn read_chunk(&mut self) -> IoResult<()> {
+        let remaining = self.reader.limit();
+        let chunk_length = remaining.min(self.chunk_size as u64 + GCM_TAG_SIZE as u64) as usize;
+        if chunk_length < GCM_TAG_SIZE {
+            return Err(IoError::new(ErrorKind::InvalidData, Error::AuthenticationFailed));
+        }
+        let is_last = chunk_length as u64 == remaining;
+        self.in_buffer.resize(chunk_length, 0);
+        self.reader.read_exact(&mut self.in_buffer)?;
+        let (ciphertext, tag) = self.in_buffer.split_at(chunk_length - GCM_TAG_SIZE);
+        self.buffer.resize(ciphertext.len(), 0);
+        let mut cipher = encryption::aes_gcm(&self.key, &self.nonce_prefix, self.chunk_index, self.chunk_size, is_last);
+        if !cipher.decrypt(ciphertext, &mut self.buffer, tag) {
+            return Err(IoError::new(ErrorKind::InvalidData, Error::AuthenticationFailed));
+        }
+        self.position = 0;
+        self.finished = is_last;
+        self.chunk_index = self.chunk_index.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> GcmReader<R> {
+    /// Moves to the given offset of the decrypted data
+    /// 
+    /// Takes the byte range of the encrypted data within the underlying reader.
+    /// Reads and authenticates the chunk holding the offset.
+    fn seek_to(&mut self, data_range: &Range<u64>, offset: u64) -> IoResult<()> {
+        let chunk_stride = self.chunk_size as u64 + GCM_TAG_SIZE as u64;
+        let mut chunk_index = offset / self.chunk_size as u64;
+        let mut chunk_begin = data_range.start
+            + (GCM_NONCE_PREFIX_SIZE + KEY_CHECK_SIZE + 4) as u64
+            + chunk_index * chunk_stride;
+        // The end of data filling its last chunk completely lies at the end of that chunk
+        if chunk_begin == data_range.end && chunk_index > 0 {
+            chunk_index -= 1;
+            chunk_begin -= chunk_stride;
+        }
+        if chunk_begin > data_range.end || chunk_index > u32::MAX as u64 {
+            return Err(IoError::new(ErrorKind::InvalidInput, "Offset out of range"));
+        }
+        self.reader.get_mut().seek(SeekFrom::Start(chunk_begin))?;
+        self.reader.set_limit(data_range.end - chunk_begin);
+        self.chunk_index = chunk_index as u32;
+        self.read_chunk()?;
+        self.position = ((offset - chunk_index * self.chunk_size as u64) as usize).min(self.buffer.len());
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for GcmReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        while self.position == self.buffer.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let length = buf.len().min(self.buffer.len() - self.position);
+        buf[0..length].copy_from_slice(&self.buffer[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// Reads bytes of a given size that were sealed using AES-256-GCM
+/// 
+/// Used for decrypting the directory and file headers.
+pub fn read_sealed<R: Read>(reader: R, size: u64, key: &Key) -> Result<Vec<u8>> {
+    let mut gcm_reader = GcmReader::new(reader.take(size), key)?;
+    let mut bytes = vec![];
+    gcm_reader.read_to_end(&mut bytes)
+        .map_err(|error| match error.kind() {
+            ErrorKind::InvalidData => Error::AuthenticationFailed,
+            _ => Error::Io(error)
+        })?;
+    Ok(bytes)
+}
+
+/// CryptReader enum
+/// 
+/// Wraps the data of a single file in one of the supported encryption algorithms.
+pub enum CryptReader<'r, R: Read + 'r> {
+    /// Raw. No encryption.
+    Raw(Take<&'r mut R>),
+    /// AES-256 encryption.
+    AES(AesReader<Take<&'r mut R>>),
+    /// AES-256-GCM authenticated encryption.
+    AESGCM(GcmReader<&'r mut R>)
+}
+
+impl<'r, R: Read + 'r> CryptReader<'r, R> {
+    /// Creates a new CryptReader with a given type
+    /// 
+    /// Wraps a reader that is positioned at the start of the data and limited to its size.
+    pub fn new(reader: Take<&'r mut R>, encryption_type: Encryption, key_opt: Option<&Key>) -> Result<Self> {
+        match encryption_type {
+            Encryption::None => Ok(CryptReader::Raw(reader)),
+            Encryption::AES => {
+                let key = key_opt.ok_or(Error::KeyRequired)?;
+                Ok(CryptReader::AES(AesReader::new(reader, key)?))
+            },
+            Encryption::AESGCM => {
+                let key = key_opt.ok_or(Error::KeyRequired)?;
+                Ok(CryptReader::AESGCM(GcmReader::new(reader, key)?))
+            },
+            other => Err(Error::UnsupportedEncryption(other))
+        }
+    }
+}
+
+impl<'r, R: Read + Seek + 'r> CryptReader<'r, R> {
+    /// Moves to the given offset of the decrypted data
+    /// 
+    /// Takes the byte range of the encrypted data within the underlying reader.
+    pub fn seek_to(&mut self, data_range: &Range<u64>, offset: u64) -> IoResult<()> {
+        match self {
+            CryptReader::Raw(reader) => {
+                let position = data_range.start.saturating_add(offset).min(data_range.end);
+                reader.get_mut().seek(SeekFrom::Start(position))?;
+                reader.set_limit(data_range.end - position);
+            },
+            CryptReader::AES(aes_reader) => {
+                let header_size = (AES_IV_SIZE + KEY_CHECK_SIZE) as u64;
+                let position = data_range.start.saturating_add(header_size).saturating_add(offset).min(data_range.end);
+                aes_reader.reader.get_mut().seek(SeekFrom::Start(position))?;
+                aes_reader.reader.set_limit(data_range.end - position);
+                aes_reader.restart_at(offset);
+            },
+            CryptReader::AESGCM(gcm_reader) => gcm_reader.seek_to(data_range, offset)?
+        }
+        Ok(())
+    }
+}
+
+impl<'r, R: Read + 'r> Read for CryptReader<'r, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let reader_ref: &mut dyn Read = match self {
+            CryptReader::Raw(reader) => reader,
+            CryptReader::AES(aes_reader) => aes_reader,
+            CryptReader::AESGCM(gcm_reader) => gcm_reader
+        };
+        reader_ref.read(buf)
+    }
+}
@@ -1,14 +1,16 @@
 use crate::{
     shared::{
-        compression::Compression,
         error::{
             Error,
             Result
         },
-        file_metadata::FileMetadata
+        file_info::FileInfo,
+        file_metadata::FileMetadata,
+        encryption::Key
     },
     read::{
-        comp_reader::CompReader
+        frame_reader::FrameReader,
+        crypt_reader::CryptReader
     }
 };
 
@@ -16,8 +18,11 @@ use std::{
     io::{
         Read,
         Seek,
+        Error as IOError,
+        ErrorKind,
         Result as IOResult,
-        SeekFrom
+        SeekFrom,
+        Take
     },
     fs::Metadata,
     ops::{
@@ -26,14 +31,28 @@ use std::{
     },
 };
 
+use blake2::{
+    Blake2s,
+    Digest
+};
+
 /// Represents a single File in the archive
-pub struct File<'r, R: Read + Seek + 'r> {
+/// 
+/// Files within seekable sources can be seeked. This is cheap for uncompressed files and
+/// files compressed in independent frames, others get decompressed from the start when seeking backwards.
+pub struct File<'r, R: Read + 'r> {
     /// An internal reference to the archives' source.
-    comp_reader: CompReader<'r, R>,
+    frame_reader: FrameReader<'r, R>,
     /// A counter for how many bytes were written.
     raw_bytes_read: usize,
     /// Total size of the uncompressed data.
     raw_size: usize,
+    /// Hasher for verifying the raw checksum
+    raw_hasher: Blake2s,
+    /// Whether or not the raw checksum has been verified yet
+    raw_verified: bool,
+    /// Information about this file, including its checksums
+    file_info: FileInfo,
     /// Optional Metadata override
     metadata_opt: Option<FileMetadata>
 }
@@ -41,16 +60,77 @@ pub struct File<'r, R: Read + Seek + 'r> {
 impl<'r, R: Read + Seek + 'r> File<'r, R> {
     /// Creates a new File
     /// 
-    /// Given a reference to the underyling reader, a data range and the compression type
-    pub fn new(reader: &'r mut R, raw_size: u64, data_range: Range<u64>, compression_type: Compression) -> Result<File<'r, R>> {
+    /// Given a reference to the underyling reader, a data range, the file info
+    /// and the key, if the file is encrypted.
+    /// If `verify_data` is set, the checksum of the compressed/encrypted data is checked
+    /// before any decryption or decompression takes place.
+    pub fn new(reader: &'r mut R, data_range: Range<u64>, file_info: FileInfo, key_opt: Option<&Key>, verify_data: bool) -> Result<File<'r, R>> {
+        if verify_data {
+            Self::verify_data_checksum(reader, data_range.clone(), &file_info)?;
+        }
+        let read_error = |source| Error::CantReadFile {
+            path: file_info.filename.clone(),
+            offset: data_range.start,
+            source
+        };
+        reader.seek(SeekFrom::Start(data_range.start))
+            .map_err(read_error)?;
+        let data = reader.take(data_range.end - data_range.start);
+        Self::from_data(data, file_info, key_opt, data_range.start)
+    }
+
+    /// Hashes the compressed data and compares it against the data checksum
+    fn verify_data_checksum(reader: &mut R, data_range: Range<u64>, file_info: &FileInfo) -> Result<()> {
+        let read_error = |source| Error::CantReadFile {
+            path: file_info.filename.clone(),
+            offset: data_range.start,
+            source
+        };
         reader.seek(SeekFrom::Start(data_range.start))
-            .map_err(|_| Error::CantReadFile)?;
-        let comp_reader: CompReader<'r, R> = CompReader::new(reader, compression_type);
+            .map_err(read_error)?;
+        let mut hasher = Blake2s::new();
+        let mut buffer = [0u8; 8192];
+        let mut remaining = data_range.end - data_range.start;
+        while remaining > 0 {
+            let chunk_size = remaining.min(buffer.len() as u64) as usize;
+            reader.read_exact(&mut buffer[0..chunk_size])
+                .map_err(read_error)?;
+            hasher.input(&buffer[0..chunk_size]);
+            remaining -= chunk_size as u64;
+        }
+        let data_checksum: [u8; 32] = hasher.result().into();
+        if data_checksum != file_info.data_checksum {
+            return Err(Error::DataChecksumMismatch(file_info.filename.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl<'r, R: Read + 'r> File<'r, R> {
+    /// Creates a new File from a reader limited to its data
+    /// 
+    /// The reader has to be positioned at the start of the data, which is at the given offset.
+    pub(crate) fn from_data(data: Take<&'r mut R>, file_info: FileInfo, key_opt: Option<&Key>, data_begin: u64) -> Result<File<'r, R>> {
+        let crypt_reader = CryptReader::new(data, file_info.encryption.clone(), key_opt)
+            .map_err(|error| error.map_io(|source| Error::CantReadFile {
+                path: file_info.filename.clone(),
+                offset: data_begin,
+                source
+            }))?;
+        let frame_reader = FrameReader::new(crypt_reader, &file_info, data_begin..data_begin + file_info.data_size)
+            .map_err(|error| error.map_io(|source| Error::CantReadFile {
+                path: file_info.filename.clone(),
+                offset: data_begin,
+                source
+            }))?;
         Ok(
             Self {
-                comp_reader: comp_reader,
+                frame_reader,
                 raw_bytes_read: 0,
-                raw_size: raw_size as usize,
+                raw_size: file_info.raw_size as usize,
+                raw_hasher: Blake2s::new(),
+                raw_verified: false,
+                file_info,
                 metadata_opt: None
             }
         )
@@ -61,23 +141,72 @@ impl<'r, R: Read + Seek + 'r> File<'r, R> {
         self.metadata_opt = Some(metadata_ref.into());
         self
     }
+
+    /// Gets the info of this file
+    pub fn get_file_info(&self) -> &FileInfo {
+        &self.file_info
+    }
+
+    /// Compares the hash of all read bytes against the raw checksum
+    /// 
+    /// Only does so once, after the last byte has been read.
+    fn verify_raw_checksum(&mut self) -> IOResult<()> {
+        if self.raw_verified {
+            return Ok(());
+        }
+        self.raw_verified = true;
+        let raw_checksum: [u8; 32] = self.raw_hasher.result_reset().into();
+        if raw_checksum != self.file_info.raw_checksum {
+            return Err(IOError::new(ErrorKind::InvalidData, Error::RawChecksumMismatch(self.file_info.filename.clone())));
+        }
+        Ok(())
+    }
 }
 
-impl<'r, R: Read + Seek + 'r> Read for File<'r, R> {
+impl<'r, R: Read + 'r> Read for File<'r, R> {
     fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
         let buffer_length = buf.len();
-        let remaining_data = self.raw_size - self.raw_bytes_read;
+        let remaining_data = self.raw_size.saturating_sub(self.raw_bytes_read);
         let read_length = if buffer_length > remaining_data {
             remaining_data
         } else {
             buffer_length
         };
-        if read_length <= 0 {
+        if read_length == 0 {
+            if remaining_data == 0 {
+                self.verify_raw_checksum()?;
+            }
             Ok(0)
         } else {
-            self.comp_reader.read_exact(&mut buf[0..read_length])?;
+            self.frame_reader.read_exact(&mut buf[0..read_length])?;
+            self.raw_hasher.input(&buf[0..read_length]);
             self.raw_bytes_read += read_length;
+            if self.raw_bytes_read == self.raw_size {
+                self.verify_raw_checksum()?;
+            }
             Ok(read_length)
         }
     }
-}
\ No newline at end of file
+}
+
+impl<'r, R: Read + Seek + 'r> Seek for File<'r, R> {
+    /// Seeks within the uncompressed data
+    /// 
+    /// The raw checksum covers the data as a whole, so it only gets verified if reading starts at the beginning.
+    fn seek(&mut self, pos: SeekFrom) -> IOResult<u64> {
+        let position_opt = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => (self.raw_size as u64).checked_add_signed(delta),
+            SeekFrom::Current(delta) => (self.raw_bytes_read as u64).checked_add_signed(delta)
+        };
+        let position = position_opt
+            .ok_or_else(|| IOError::new(ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))?;
+        if position != self.raw_bytes_read as u64 {
+            self.frame_reader.seek_to(position)?;
+            self.raw_bytes_read = position as usize;
+            self.raw_hasher = Blake2s::new();
+            self.raw_verified = position != 0;
+        }
+        Ok(position)
+    }
+}
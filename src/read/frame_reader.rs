@@ -0,0 +1,173 @@
+use crate::{
+    shared::{
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        file_info::FileInfo,
+        seek_table::SeekTable,
+        error::{
+            Error,
+            Result
+        }
+    },
+    read::{
+        comp_reader::CompReader,
+        crypt_reader::CryptReader
+    }
+};
+
+use std::{
+    io::{
+        Read,
+        Seek,
+        Take,
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
+    },
+    ops::Range
+};
+
+/// FrameReader struct
+/// 
+/// Decompresses the data of a single file. If the file was compressed in independent frames,
+/// every frame gets its own decoder, limited to the frame's compressed bytes.
+/// Otherwise, a single decoder covers the whole file.
+pub struct FrameReader<'r, R: Read + 'r> {
+    /// The decoder of the current frame
+    comp_reader: CompReader<Take<CryptReader<'r, R>>>,
+    /// The compression algorithm
+    compression: Compression,
+    /// The compression parameters the data was compressed with
+    compression_params: CompressionParams,
+    /// The seek table, if the file was compressed in frames
+    seek_table_opt: Option<SeekTable>,
+    /// Total size of the uncompressed data
+    raw_size: u64,
+    /// Read position within the uncompressed data
+    position: u64,
+    /// Index of the current frame
+    frame_index: usize,
+    /// Byte range of the stored data within the underlying reader
+    data_range: Range<u64>
+}
+
+impl<'r, R: Read + 'r> FrameReader<'r, R> {
+    /// Creates a new FrameReader, wrapping a decrypting reader positioned at the start of the data
+    /// 
+    /// Fails if the file's compression isn't supported.
+    pub fn new(crypt_reader: CryptReader<'r, R>, file_info: &FileInfo, data_range: Range<u64>) -> Result<Self> {
+        let mut frame_reader = Self {
+            comp_reader: CompReader::None,
+            compression: file_info.compression.clone(),
+            compression_params: file_info.compression_params.clone(),
+            seek_table_opt: file_info.seek_table.clone(),
+            raw_size: file_info.raw_size,
+            position: 0,
+            frame_index: 0,
+            data_range
+        };
+        frame_reader.open_frame(0, crypt_reader)?;
+        Ok(frame_reader)
+    }
+
+    /// Creates the decoder for the given frame, starting at the decrypting reader's position
+    fn open_frame(&mut self, frame_index: usize, crypt_reader: CryptReader<'r, R>) -> Result<()> {
+        let limit = match self.seek_table_opt.as_ref() {
+            Some(seek_table) => seek_table.get_frame_range(frame_index)
+                .map(|range| range.end.saturating_sub(range.start))
+                .unwrap_or(0),
+            None => u64::MAX
+        };
+        self.frame_index = frame_index;
+        self.comp_reader = CompReader::new(crypt_reader.take(limit), self.compression.clone(), &self.compression_params)?;
+        Ok(())
+    }
+
+    /// Takes the current frame's decoder apart, returning the frame's compressed data
+    fn take_frame_data(&mut self) -> IoResult<Take<CryptReader<'r, R>>> {
+        match std::mem::replace(&mut self.comp_reader, CompReader::None) {
+            CompReader::None => Err(IoError::other("Reader misconfigured!")),
+            comp_reader => Ok(comp_reader.into_inner())
+        }
+    }
+
+    /// Gets the end of the current frame within the uncompressed data
+    fn get_frame_end(&self) -> u64 {
+        match self.seek_table_opt.as_ref() {
+            Some(seek_table) => (self.frame_index as u64 + 1).saturating_mul(seek_table.frame_size).min(self.raw_size),
+            None => u64::MAX
+        }
+    }
+
+    /// Moves on to the next frame, skipping whatever is left of the current one
+    fn next_frame(&mut self) -> IoResult<()> {
+        let mut frame_data = self.take_frame_data()?;
+        std::io::copy(&mut frame_data, &mut std::io::sink())?;
+        self.open_frame(self.frame_index + 1, frame_data.into_inner())
+            .map_err(Error::into_io)
+    }
+}
+
+impl<'r, R: Read + Seek + 'r> FrameReader<'r, R> {
+    /// Moves to the given offset of the uncompressed data
+    /// 
+    /// Uncompressed files are seeked directly. Files compressed in frames only decompress the frame
+    /// holding the offset. Any other file gets decompressed from the start when seeking backwards.
+    /// Offsets past the end are clamped to the end.
+    pub fn seek_to(&mut self, offset: u64) -> IoResult<()> {
+        let offset = offset.min(self.raw_size);
+        if self.compression == Compression::None {
+            let mut crypt_reader = self.take_frame_data()?.into_inner();
+            crypt_reader.seek_to(&self.data_range, offset)?;
+            self.open_frame(0, crypt_reader)
+                .map_err(Error::into_io)?;
+            self.position = offset;
+            return Ok(());
+        }
+        let restart_opt = match self.seek_table_opt.as_ref() {
+            Some(seek_table) if seek_table.get_frame_count() > 0 => {
+                let frame_index = seek_table.get_frame_index(offset);
+                if frame_index == self.frame_index && offset >= self.position {
+                    None
+                } else {
+                    let frame_begin = seek_table.get_frame_range(frame_index).map(|range| range.start).unwrap_or(0);
+                    Some((frame_index, frame_begin, frame_index as u64 * seek_table.frame_size))
+                }
+            },
+            Some(_) => None,
+            None if offset < self.position => Some((0, 0, 0)),
+            None => None
+        };
+        if let Some((frame_index, frame_begin, raw_begin)) = restart_opt {
+            let mut crypt_reader = self.take_frame_data()?.into_inner();
+            crypt_reader.seek_to(&self.data_range, frame_begin)?;
+            self.open_frame(frame_index, crypt_reader)
+                .map_err(Error::into_io)?;
+            self.position = raw_begin;
+        }
+        let skip = offset - self.position;
+        let skipped = std::io::copy(&mut (&mut *self).take(skip), &mut std::io::sink())?;
+        if skipped != skip {
+            return Err(IoError::from(ErrorKind::UnexpectedEof));
+        }
+        Ok(())
+    }
+}
+
+impl<'r, R: Read + 'r> Read for FrameReader<'r, R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if self.position >= self.get_frame_end() {
+            let frame_count = self.seek_table_opt.as_ref().map(SeekTable::get_frame_count).unwrap_or(0);
+            if self.frame_index + 1 >= frame_count {
+                return Ok(0);
+            }
+            self.next_frame()?;
+        }
+        let length = (buf.len() as u64).min(self.get_frame_end() - self.position) as usize;
+        let read = self.comp_reader.read(&mut buf[0..length])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
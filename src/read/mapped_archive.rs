@@ -0,0 +1,134 @@
+use crate::{
+    shared::{
+        file_info::FileInfo,
+        encryption::Key,
+        key_derivation::KeyDerivation,
+        format_version::FormatVersion,
+        error::Result
+    },
+    read::{
+        archive::Archive,
+        shared_archive::SharedArchive,
+        read_at::PositionalReader
+    }
+};
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    ops::Range
+};
+
+use memmap2::Mmap;
+
+/// The MappedArchive struct
+///
+/// An archive read from a memory-mapped file. Files stored as-is, without compression or encryption,
+/// are borrowed straight from the mapping. Any other file is decompressed/decrypted from the mapped bytes,
+/// so reading never makes a system call. Like a `SharedArchive`, it can be read from any number of threads at once.
+///
+/// The file must not be modified or truncated while the archive is alive, as the mapping would change
+/// underneath it. The file itself can be closed once the archive is created.
+pub struct MappedArchive {
+    /// The archive, reading from the mapping
+    archive: SharedArchive<Mmap>
+}
+
+impl MappedArchive {
+    /// Creates a new mapped archive, mapping the given file
+    ///
+    /// Fails with `Error::KeyRequired` if the directory is encrypted.
+    pub fn new(file: &File) -> Result<Self> {
+        let archive = SharedArchive::new(map_file(file)?)?;
+        Ok(Self { archive })
+    }
+
+    /// Creates a new mapped archive with a given encryption key, mapping the given file
+    pub fn new_with_key(file: &File, key: Key) -> Result<Self> {
+        let archive = SharedArchive::new_with_key(map_file(file)?, key)?;
+        Ok(Self { archive })
+    }
+
+    /// Creates a new mapped archive from a password protected file, mapping it
+    ///
+    /// The key is derived from the password using the parameters stored in the archive header.
+    pub fn new_with_password(file: &File, password: &str) -> Result<Self> {
+        let archive = SharedArchive::new_with_password(map_file(file)?, password)?;
+        Ok(Self { archive })
+    }
+
+    /// ...with data checksum verification enabled or disabled
+    ///
+    /// See `Archive::with_data_verification`. Borrowed files get their checksum verified on every access.
+    pub fn with_data_verification(mut self, verify_data: bool) -> Self {
+        self.archive = self.archive.with_data_verification(verify_data);
+        self
+    }
+
+    /// Gets the stored data of a file, borrowed from the mapping
+    ///
+    /// Fails with `Error::NotStored` if the file is compressed or encrypted.
+    pub fn get_stored(&self, path: &str) -> Result<&[u8]> {
+        self.archive.get_reader().get_stored_slice(self.get_bytes(), path)
+    }
+
+    /// Gets the uncompressed/decrypted data of a file
+    ///
+    /// Files stored as-is are borrowed from the mapping, any other file gets decompressed/decrypted into a new buffer.
+    pub fn get_data(&self, path: &str) -> Result<Cow<'_, [u8]>> {
+        self.archive.get_reader().get_data(self.get_bytes(), path)
+    }
+
+    /// Gets a new reader of the archive, for streaming files
+    ///
+    /// See `SharedArchive::get_reader`.
+    pub fn get_reader(&self) -> Archive<PositionalReader<Mmap>> {
+        self.archive.get_reader()
+    }
+
+    /// Returns a boolean indicating whether or not the directory and file headers are encrypted
+    pub fn is_index_encrypted(&self) -> bool {
+        self.archive.is_index_encrypted()
+    }
+
+    /// Gets the on-disk format version of the archive
+    pub fn get_format_version(&self) -> FormatVersion {
+        self.archive.get_format_version()
+    }
+
+    /// Gets the key derivation parameters, if the archive is password protected
+    pub fn get_key_derivation(&self) -> Option<&KeyDerivation> {
+        self.archive.get_key_derivation()
+    }
+
+    /// Lists all files
+    pub fn get_file_list(&self) -> Vec<String> {
+        self.archive.get_file_list()
+    }
+
+    /// Gets a files info by path
+    pub fn get_file_info(&self, path: &str) -> Result<FileInfo> {
+        self.archive.get_file_info(path)
+    }
+
+    /// Reads a byte range of a file's uncompressed/decrypted data
+    ///
+    /// See `Archive::read_range`.
+    pub fn read_range(&self, path: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        self.archive.read_range(path, range)
+    }
+
+    /// Gets the mapped bytes of the whole archive
+    fn get_bytes(&self) -> &[u8] {
+        self.archive.get_source()
+    }
+}
+
+/// Maps the whole file into memory, read-only
+fn map_file(file: &File) -> Result<Mmap> {
+    // SAFETY: the mapping is only ever read through shared slices, and `MappedArchive` requires
+    // the file to stay unmodified while it's mapped. Mapping itself is sound, but if another process
+    // truncates or rewrites the file anyway, reads see the changed bytes or fault.
+    let mapping = unsafe { Mmap::map(file)? };
+    Ok(mapping)
+}
@@ -1,6 +1,27 @@
 /// Archive struct
 pub mod archive;
+/// Archive shared between threads
+pub mod shared_archive;
+/// Memory-mapped archive
+pub mod mapped_archive;
+/// Archive borrowed from bytes in memory
+pub mod slice_archive;
+/// Positional reads of shared sources
+pub mod read_at;
 /// File stream struct
 pub mod file;
+/// Sequential archive reader for sources that can't seek
+pub mod stream_reader;
+/// Position tracking reader stream wrapper
+pub mod position_reader;
 /// Compression reader stream wrapper
 pub mod comp_reader;
+/// Frame-by-frame decompression & seeking
+pub mod frame_reader;
+/// Archive verification report
+pub mod verify;
+/// Decryption reader stream wrapper
+pub mod crypt_reader;
+
+/// Decoding of every supported on-disk format revision
+pub mod versioned;
\ No newline at end of file
@@ -0,0 +1,45 @@
+use std::{
+    io::{
+        Read,
+        Result as IoResult
+    }
+};
+
+/// PositionReader struct
+/// 
+/// Wraps a plain reader and keeps track of the number of bytes read,
+/// so archives can be read from sources that can't seek, like pipes or sockets.
+pub struct PositionReader<R: Read> {
+    /// The underlying reader
+    reader: R,
+    /// The number of bytes read so far
+    position: u64
+}
+
+impl<R: Read> PositionReader<R> {
+    /// Creates a new instance, wrapping the given reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0
+        }
+    }
+
+    /// Gets the number of bytes read so far
+    pub fn get_position(&self) -> u64 {
+        self.position
+    }
+
+    /// Unwraps the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for PositionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let read = self.reader.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
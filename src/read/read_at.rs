@@ -0,0 +1,128 @@
+use std::{
+    io::{
+        Read,
+        Seek,
+        SeekFrom,
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
+    },
+    sync::Arc
+};
+
+use memmap2::Mmap;
+
+/// ReadAt trait
+/// 
+/// A source that can be read at any offset through a shared reference,
+/// so any number of readers can use it at once.
+pub trait ReadAt {
+    /// Reads bytes starting at the given offset, returning the number of bytes read
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> IoResult<usize>;
+
+    /// Gets the size of the source
+    fn get_size(&self) -> IoResult<u64>;
+}
+
+/// Every read passes its own offset, so the file's cursor is never relied upon
+#[cfg(any(unix, windows))]
+impl ReadAt for std::fs::File {
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> IoResult<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> IoResult<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+
+    fn get_size(&self) -> IoResult<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> IoResult<usize> {
+        Ok(read_slice_at(self, buf, offset))
+    }
+
+    fn get_size(&self) -> IoResult<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+/// Reads are copies out of the mapping, so they never make a system call
+impl ReadAt for Mmap {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> IoResult<usize> {
+        Ok(read_slice_at(self, buf, offset))
+    }
+
+    fn get_size(&self) -> IoResult<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+/// Copies bytes out of a slice, starting at the given offset
+fn read_slice_at(bytes: &[u8], buf: &mut [u8], offset: u64) -> usize {
+    let begin = (offset.min(bytes.len() as u64)) as usize;
+    let length = buf.len().min(bytes.len() - begin);
+    buf[0..length].copy_from_slice(&bytes[begin..begin + length]);
+    length
+}
+
+/// PositionalReader struct
+/// 
+/// A cursor over a shared ReadAt source. Every reader keeps its own position,
+/// so cloning it yields an independent reader of the same source.
+pub struct PositionalReader<S: ReadAt> {
+    /// The shared source
+    source: Arc<S>,
+    /// The current position
+    position: u64
+}
+
+impl<S: ReadAt> PositionalReader<S> {
+    /// Creates a new instance at the start of the given source
+    pub fn new(source: Arc<S>) -> Self {
+        Self {
+            source,
+            position: 0
+        }
+    }
+
+    /// Gets the shared source
+    pub fn get_source(&self) -> &Arc<S> {
+        &self.source
+    }
+}
+
+impl<S: ReadAt> Clone for PositionalReader<S> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source.clone(),
+            position: self.position
+        }
+    }
+}
+
+impl<S: ReadAt> Read for PositionalReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let read = self.source.read_at(buf, self.position)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<S: ReadAt> Seek for PositionalReader<S> {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let position_opt = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.source.get_size()?.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta)
+        };
+        self.position = position_opt
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))?;
+        Ok(self.position)
+    }
+}
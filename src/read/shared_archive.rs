@@ -0,0 +1,108 @@
+use crate::{
+    shared::{
+        file_info::FileInfo,
+        encryption::Key,
+        key_derivation::KeyDerivation,
+        format_version::FormatVersion,
+        error::Result
+    },
+    read::{
+        archive::Archive,
+        read_at::{
+            ReadAt,
+            PositionalReader
+        }
+    }
+};
+
+use std::{
+    ops::Range,
+    sync::Arc
+};
+
+/// The SharedArchive struct
+/// 
+/// An archive that can be read from any number of threads at once. It reads through positional I/O,
+/// so all readers share a single source, e.g. one open file, without ever seeking it.
+/// Entries are streamed through readers, which are cheap to create, since they share the directory.
+pub struct SharedArchive<S: ReadAt> {
+    /// The archive, reading at the start of the shared source
+    archive: Archive<PositionalReader<S>>
+}
+
+impl<S: ReadAt> SharedArchive<S> {
+    /// Creates a new shared archive, wrapping the given source
+    /// 
+    /// Fails with `Error::KeyRequired` if the directory is encrypted.
+    pub fn new(source: S) -> Result<Self> {
+        let archive = Archive::new(PositionalReader::new(Arc::new(source)))?;
+        Ok(Self { archive })
+    }
+
+    /// Creates a new shared archive with a given encryption key, wrapping the given source
+    pub fn new_with_key(source: S, key: Key) -> Result<Self> {
+        let archive = Archive::new_with_key(PositionalReader::new(Arc::new(source)), key)?;
+        Ok(Self { archive })
+    }
+
+    /// Creates a new shared archive from a password protected source
+    /// 
+    /// The key is derived from the password using the parameters stored in the archive header.
+    pub fn new_with_password(source: S, password: &str) -> Result<Self> {
+        let archive = Archive::new_with_password(PositionalReader::new(Arc::new(source)), password)?;
+        Ok(Self { archive })
+    }
+
+    /// ...with data checksum verification enabled or disabled for all readers
+    /// 
+    /// See `Archive::with_data_verification`.
+    pub fn with_data_verification(mut self, verify_data: bool) -> Self {
+        self.archive = self.archive.with_data_verification(verify_data);
+        self
+    }
+
+    /// Gets a new reader of the archive
+    /// 
+    /// Every reader reads independently of all others, so each thread can stream entries through its own.
+    pub fn get_reader(&self) -> Archive<PositionalReader<S>> {
+        let source = PositionalReader::new(self.archive.get_source().get_source().clone());
+        self.archive.with_source(source)
+    }
+
+    /// Gets the shared source
+    pub(crate) fn get_source(&self) -> &S {
+        self.archive.get_source().get_source()
+    }
+
+    /// Returns a boolean indicating whether or not the directory and file headers are encrypted
+    pub fn is_index_encrypted(&self) -> bool {
+        self.archive.is_index_encrypted()
+    }
+
+    /// Gets the on-disk format version of the archive
+    pub fn get_format_version(&self) -> FormatVersion {
+        self.archive.get_format_version()
+    }
+
+    /// Gets the key derivation parameters, if the archive is password protected
+    pub fn get_key_derivation(&self) -> Option<&KeyDerivation> {
+        self.archive.get_key_derivation()
+    }
+
+    /// Lists all files
+    pub fn get_file_list(&self) -> Vec<String> {
+        self.archive.get_file_list()
+    }
+
+    /// Gets a files info by path
+    pub fn get_file_info(&self, path: &str) -> Result<FileInfo> {
+        self.get_reader().get_file_info(path)
+    }
+
+    /// Reads a byte range of a file's uncompressed/decrypted data
+    /// 
+    /// See `Archive::read_range`.
+    pub fn read_range(&self, path: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        self.get_reader().read_range(path, range)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    shared::{
+        file_info::FileInfo,
+        encryption::Key,
+        key_derivation::KeyDerivation,
+        format_version::FormatVersion,
+        error::Result
+    },
+    read::archive::Archive
+};
+
+use std::{
+    borrow::Cow,
+    io::Cursor,
+    ops::Range
+};
+
+/// The SliceArchive struct
+///
+/// An archive read from bytes in memory, e.g. ones embedded with `include_bytes!`. The header and directory
+/// are parsed once, every read goes through a shared reference, so it can be used from statics and any number of threads.
+/// Files stored as-is, without compression or encryption, are borrowed from the bytes for as long as those live.
+pub struct SliceArchive<'a> {
+    /// The bytes of the whole archive
+    bytes: &'a [u8],
+    /// The archive, reading at the start of the bytes
+    archive: Archive<Cursor<&'a [u8]>>
+}
+
+impl<'a> SliceArchive<'a> {
+    /// Creates a new slice archive, wrapping the given bytes
+    ///
+    /// Fails with `Error::KeyRequired` if the directory is encrypted.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        let archive = Archive::new(Cursor::new(bytes))?;
+        Ok(Self { bytes, archive })
+    }
+
+    /// Creates a new slice archive with a given encryption key, wrapping the given bytes
+    pub fn new_with_key(bytes: &'a [u8], key: Key) -> Result<Self> {
+        let archive = Archive::new_with_key(Cursor::new(bytes), key)?;
+        Ok(Self { bytes, archive })
+    }
+
+    /// Creates a new slice archive from password protected bytes
+    ///
+    /// The key is derived from the password using the parameters stored in the archive header.
+    pub fn new_with_password(bytes: &'a [u8], password: &str) -> Result<Self> {
+        let archive = Archive::new_with_password(Cursor::new(bytes), password)?;
+        Ok(Self { bytes, archive })
+    }
+
+    /// ...with data checksum verification enabled or disabled
+    ///
+    /// See `Archive::with_data_verification`. Borrowed files get their checksum verified on every access.
+    pub fn with_data_verification(mut self, verify_data: bool) -> Self {
+        self.archive = self.archive.with_data_verification(verify_data);
+        self
+    }
+
+    /// Gets the stored data of a file, borrowed from the archive's bytes
+    ///
+    /// Fails with `Error::NotStored` if the file is compressed or encrypted.
+    pub fn get_stored(&self, path: &str) -> Result<&'a [u8]> {
+        self.get_reader().get_stored_slice(self.bytes, path)
+    }
+
+    /// Gets the uncompressed/decrypted data of a file
+    ///
+    /// Files stored as-is are borrowed from the archive's bytes, any other file gets decompressed/decrypted into a new buffer.
+    pub fn get_data(&self, path: &str) -> Result<Cow<'a, [u8]>> {
+        self.get_reader().get_data(self.bytes, path)
+    }
+
+    /// Gets a new reader of the archive, for streaming files
+    ///
+    /// Every reader reads independently of all others. Nothing gets parsed, the directory is shared.
+    pub fn get_reader(&self) -> Archive<Cursor<&'a [u8]>> {
+        self.archive.with_source(Cursor::new(self.bytes))
+    }
+
+    /// Returns a boolean indicating whether or not the directory and file headers are encrypted
+    pub fn is_index_encrypted(&self) -> bool {
+        self.archive.is_index_encrypted()
+    }
+
+    /// Gets the on-disk format version of the archive
+    pub fn get_format_version(&self) -> FormatVersion {
+        self.archive.get_format_version()
+    }
+
+    /// Gets the key derivation parameters, if the archive is password protected
+    pub fn get_key_derivation(&self) -> Option<&KeyDerivation> {
+        self.archive.get_key_derivation()
+    }
+
+    /// Lists all files
+    pub fn get_file_list(&self) -> Vec<String> {
+        self.archive.get_file_list()
+    }
+
+    /// Gets a files info by path
+    pub fn get_file_info(&self, path: &str) -> Result<FileInfo> {
+        self.get_reader().get_file_info(path)
+    }
+
+    /// Reads a byte range of a file's uncompressed/decrypted data
+    ///
+    /// See `Archive::read_range`.
+    pub fn read_range(&self, path: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        self.get_reader().read_range(path, range)
+    }
+}
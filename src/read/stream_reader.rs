@@ -0,0 +1,194 @@
+use crate::{
+    shared::{
+        archive_header::ArchiveHeader,
+        encryption::{
+            Encryption,
+            Key
+        },
+        format_version::{
+            FormatVersion,
+            FORMAT_VERSION_SIZE
+        },
+        frame::{
+            MARKER_SIZE,
+            ENTRY_MARKER,
+            DIRECTORY_MARKER
+        },
+        encoding::Decode,
+        key_derivation::KeyDerivation,
+        error::{
+            Error,
+            Result
+        }
+    },
+    read::{
+        file::File,
+        versioned,
+        crypt_reader,
+        position_reader::PositionReader
+    }
+};
+
+use std::{
+    io::{
+        Read,
+        Error as IoError,
+        ErrorKind
+    }
+};
+
+use tracing::{
+    debug,
+    debug_span
+};
+
+/// The StreamReader struct
+///
+/// Reads an archive front to back, from any source, including ones that can't seek.
+/// Entries are yielded in the order they are stored in. The directory isn't needed for that,
+/// so reading stops at the directory marker. Revision 0 archives can't be read sequentially.
+pub struct StreamReader<R: Read> {
+    /// The source - e.g. stdin or a socket
+    source: PositionReader<R>,
+    /// The archive header
+    header: ArchiveHeader,
+    /// The on-disk format version
+    format_version: FormatVersion,
+    /// Optional key for encrypted files
+    key_opt: Option<Key>,
+    /// End of the current entry's data, which gets skipped if it hasn't been read
+    data_end: u64,
+    /// Whether the directory marker has been reached
+    finished: bool
+}
+
+impl<R: Read> StreamReader<R> {
+    /// Creates a new stream reader, wrapping the given source
+    pub fn new(source: R) -> Result<Self> {
+        Self::open(source, None, None)
+    }
+
+    /// Creates a new stream reader with a given encryption key, wrapping the given source
+    pub fn new_with_key(source: R, key: Key) -> Result<Self> {
+        Self::open(source, Some(key), None)
+    }
+
+    /// Creates a new stream reader from a password protected source
+    ///
+    /// The key is derived from the password using the parameters stored in the archive header.
+    pub fn new_with_password(source: R, password: &str) -> Result<Self> {
+        Self::open(source, None, Some(password))
+    }
+
+    /// Reads the magic bytes, the format version and the archive header
+    fn open(source: R, mut key_opt: Option<Key>, password_opt: Option<&str>) -> Result<Self> {
+        let _span = debug_span!("open_stream").entered();
+        let mut source = PositionReader::new(source);
+        let mut magic_bytes = [0u8; 3];
+        source.read_exact(&mut magic_bytes)
+            .map_err(Error::CantReadMagicBytes)?;
+        if &magic_bytes != b"VAR" {
+            return Err(Error::IncorrectMagicBytes(magic_bytes));
+        }
+        let mut format_version_bytes = [0u8; FORMAT_VERSION_SIZE];
+        source.read_exact(&mut format_version_bytes)
+            .map_err(Error::CantReadHeader)?;
+        let format_version = if versioned::is_revision_0(&format_version_bytes) {
+            FormatVersion::REVISION_0
+        } else {
+            FormatVersion::from_bytes(format_version_bytes)
+        };
+        if !format_version.is_supported() {
+            return Err(Error::VersionMismatch {
+                found: format_version,
+                supported: FormatVersion::CURRENT
+            });
+        }
+        if !format_version.is_streamable() {
+            return Err(Error::NotStreamable(format_version));
+        }
+        let (header, _) = versioned::decode_archive_header(format_version, &mut source)
+            .map_err(Error::CorruptHeader)?;
+        if let Some(password) = password_opt {
+            let key = header.key_derivation.as_ref()
+                .ok_or(Error::NotPasswordProtected)?
+                .derive_key(password)?;
+            key_opt = Some(key);
+        }
+        debug!(format_version = %format_version, "Opened archive stream");
+        Ok(
+            Self {
+                data_end: source.get_position(),
+                source,
+                header,
+                format_version,
+                key_opt,
+                finished: false
+            }
+        )
+    }
+
+    /// Gets the on-disk format version of the archive
+    pub fn get_format_version(&self) -> FormatVersion {
+        self.format_version
+    }
+
+    /// Gets the key derivation parameters, if the archive is password protected
+    pub fn get_key_derivation(&self) -> Option<&KeyDerivation> {
+        self.header.key_derivation.as_ref()
+    }
+
+    /// Gets the next entry, or None once all entries have been read
+    ///
+    /// Whatever hasn't been read of the previous entry gets skipped.
+    /// The file info of the returned entry holds its path as the filename.
+    pub fn next_entry(&mut self) -> Result<Option<File<'_, PositionReader<R>>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let skip_begin = self.source.get_position();
+        let remaining = self.data_end.saturating_sub(skip_begin);
+        std::io::copy(&mut (&mut self.source).take(remaining), &mut std::io::sink())
+            .map_err(|source| Error::CorruptEntryFrame { offset: skip_begin, source: source.into() })?;
+        let frame_begin = self.source.get_position();
+        let frame_error = |source: IoError| Error::CorruptEntryFrame {
+            offset: frame_begin,
+            source: source.into()
+        };
+        let mut marker = [0u8; MARKER_SIZE];
+        self.source.read_exact(&mut marker)
+            .map_err(frame_error)?;
+        if marker == DIRECTORY_MARKER {
+            self.finished = true;
+            return Ok(None);
+        }
+        if marker != ENTRY_MARKER {
+            return Err(frame_error(IoError::new(ErrorKind::InvalidData, "Unknown marker")));
+        }
+        let file_header_size = u64::decode(&mut self.source)
+            .map_err(frame_error)?;
+        let mut file_header_bytes = vec![];
+        (&mut self.source).take(file_header_size).read_to_end(&mut file_header_bytes)
+            .map_err(frame_error)?;
+        if file_header_bytes.len() as u64 != file_header_size {
+            return Err(frame_error(IoError::from(ErrorKind::UnexpectedEof)));
+        }
+        if self.header.index_encryption != Encryption::None {
+            let key = self.key_opt.as_ref().ok_or(Error::KeyRequired)?;
+            file_header_bytes = crypt_reader::read_sealed(&file_header_bytes[..], file_header_size, key)
+                .map_err(|error| error.map_io(frame_error))?;
+        }
+        let file_header = versioned::decode_file_header(self.format_version, &file_header_bytes)
+            .map_err(|source| Error::CorruptEntryFrame { offset: frame_begin, source })?;
+        let data_range = file_header.data_range;
+        let data_begin = self.source.get_position();
+        if data_range.start != data_begin || data_range.end < data_range.start {
+            return Err(frame_error(IoError::new(ErrorKind::InvalidData, "Data doesn't follow the file header")));
+        }
+        self.data_end = data_range.end;
+        debug!(path = %file_header.file_info.filename, data_begin, "Reading entry");
+        let data = (&mut self.source).take(data_range.end - data_range.start);
+        File::from_data(data, file_header.file_info, self.key_opt.as_ref(), data_begin)
+            .map(Some)
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    ops::Range,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    }
+};
+
+/// Describes what went wrong while verifying an entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureKind {
+    /// The file header range lies outside of the archives' entry area
+    HeaderRangeOutOfBounds(Range<u64>),
+    /// The file header couldnt be read or decoded
+    HeaderDecode,
+    /// The data range lies outside of the archives' entry area
+    DataRangeOutOfBounds(Range<u64>),
+    /// The data or header range overlaps with a range of the given entry
+    OverlappingRanges(String),
+    /// The data range doesnt match the data size in the file info
+    DataSizeMismatch {
+        /// Size according to the file info
+        expected: u64,
+        /// Size of the data range
+        actual: u64
+    },
+    /// The checksum of the compressed/encrypted data doesnt match
+    DataChecksumMismatch,
+    /// The data couldnt be decrypted, e.g. because of a missing or wrong key
+    Decryption,
+    /// The data couldnt be decompressed
+    Decompression,
+    /// The uncompressed data is shorter than the raw size in the file info
+    RawSizeMismatch {
+        /// Size according to the file info
+        expected: u64,
+        /// Number of bytes that could be decompressed
+        actual: u64
+    },
+    /// The checksum of the uncompressed/decrypted data doesnt match
+    RawChecksumMismatch
+}
+
+impl Display for FailureKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            FailureKind::HeaderRangeOutOfBounds(range) => write!(f, "file header range {:?} is out of bounds", range),
+            FailureKind::HeaderDecode => write!(f, "file header couldnt be decoded"),
+            FailureKind::DataRangeOutOfBounds(range) => write!(f, "data range {:?} is out of bounds", range),
+            FailureKind::OverlappingRanges(other) => write!(f, "overlaps with \"{}\"", other),
+            FailureKind::DataSizeMismatch { expected, actual } => write!(f, "data size is {}B, expected {}B", actual, expected),
+            FailureKind::DataChecksumMismatch => write!(f, "data checksum mismatch"),
+            FailureKind::Decryption => write!(f, "data couldnt be decrypted"),
+            FailureKind::Decompression => write!(f, "data couldnt be decompressed"),
+            FailureKind::RawSizeMismatch { expected, actual } => write!(f, "raw size is {}B, expected {}B", actual, expected),
+            FailureKind::RawChecksumMismatch => write!(f, "raw checksum mismatch")
+        }
+    }
+}
+
+/// A single failed check of an entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryFailure {
+    /// Path of the entry
+    pub path: String,
+    /// What went wrong
+    pub kind: FailureKind
+}
+
+/// The result of verifying a whole archive
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Number of entries that were checked
+    pub entries_checked: usize,
+    /// All failed checks, in the order the entries are stored
+    pub failures: Vec<EntryFailure>
+}
+
+impl VerifyReport {
+    /// Returns a boolean indicating whether or not every check passed
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Records a failed check
+    pub(crate) fn fail(&mut self, path: &str, kind: FailureKind) {
+        self.failures.push(EntryFailure {
+            path: String::from(path),
+            kind
+        });
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "Checked {} entries, {} failures", self.entries_checked, self.failures.len())?;
+        for failure in self.failures.iter() {
+            writeln!(f, "    {}: {}", failure.path, failure.kind)?;
+        }
+        Ok(())
+    }
+}
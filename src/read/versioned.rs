@@ -0,0 +1,166 @@
+use crate::{
+    shared::{
+        archive_header::ArchiveHeader,
+        directory::Directory,
+        file_header::FileHeader,
+        file_info::FileInfo,
+        file_metadata::FileMetadata,
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::Encryption,
+        format_version::{
+            FormatVersion,
+            FORMAT_VERSION_SIZE
+        },
+        encoding::Decode,
+        error::Source
+    }
+};
+
+use std::{
+    io::Read,
+    ops::Range
+};
+
+use serde::Deserialize;
+
+/// Result of decoding an on-disk structure
+pub type DecodeResult<T> = std::result::Result<T, Source>;
+
+/// Checks whether the bytes following the magic bytes belong to a revision 0 archive
+///
+/// Revision 0 archives have no format version, their header starts right after the magic bytes
+/// with the library version as an ASCII string, e.g. `0.1.0`. Every encoded format version
+/// contains at least one byte that isn't an ASCII digit or a dot.
+pub fn is_revision_0(bytes: &[u8; FORMAT_VERSION_SIZE]) -> bool {
+    bytes.iter().all(|byte| byte.is_ascii_digit() || *byte == b'.')
+}
+
+/// Decodes the archive header of the given format revision
+///
+/// Revision 0 archive headers pointed to the directory instead of the trailer.
+/// For those, the directory range gets returned along with the header.
+pub fn decode_archive_header<R: Read>(format_version: FormatVersion, mut reader: R) -> DecodeResult<(ArchiveHeader, Option<Range<u64>>)> {
+    match format_version.major {
+        0 => {
+            let header: ArchiveHeaderV0 = bincode::deserialize_from(reader)?;
+            Ok(header.into_parts())
+        },
+        _ => Ok((ArchiveHeader::decode(&mut reader)?, None))
+    }
+}
+
+/// Decodes the directory of the given format revision
+pub fn decode_directory<R: Read>(format_version: FormatVersion, mut reader: R) -> DecodeResult<Directory> {
+    match format_version.major {
+        0 => Ok(bincode::deserialize_from(reader)?),
+        _ => Ok(Directory::decode(&mut reader)?)
+    }
+}
+
+/// Decodes a file header of the given format revision
+pub fn decode_file_header(format_version: FormatVersion, mut bytes: &[u8]) -> DecodeResult<FileHeader> {
+    match format_version.major {
+        0 => {
+            let file_header: FileHeaderV0 = bincode::deserialize(bytes)?;
+            Ok(file_header.into())
+        },
+        _ => Ok(FileHeader::decode(&mut bytes)?)
+    }
+}
+
+/// Revision 0 archive header
+///
+/// Revision 0 was encoded using bincode's defaults.
+/// Had neither password protection nor index encryption.
+#[derive(Deserialize)]
+struct ArchiveHeaderV0 {
+    version: [u8; 16],
+    directory_range: Range<u64>
+}
+
+impl ArchiveHeaderV0 {
+    /// Splits the header into the current archive header and the directory range
+    fn into_parts(self) -> (ArchiveHeader, Option<Range<u64>>) {
+        let header = ArchiveHeader {
+            version: self.version,
+            key_derivation: None,
+            index_encryption: Encryption::None
+        };
+        (header, Some(self.directory_range))
+    }
+}
+
+/// Revision 0 file header
+#[derive(Deserialize)]
+struct FileHeaderV0 {
+    data_range: Range<u64>,
+    file_info: FileInfoV0
+}
+
+impl From<FileHeaderV0> for FileHeader {
+    fn from(file_header: FileHeaderV0) -> Self {
+        Self {
+            data_range: file_header.data_range,
+            file_info: file_header.file_info.into()
+        }
+    }
+}
+
+/// Revision 0 file info
+///
+/// Didn't record the compression parameters. zstd always used level 9 back then.
+#[derive(Deserialize)]
+struct FileInfoV0 {
+    raw_size: u64,
+    data_size: u64,
+    compression: Compression,
+    encryption: EncryptionV0,
+    filename: String,
+    metadata: FileMetadata,
+    data_checksum: [u8; 32],
+    raw_checksum: [u8; 32]
+}
+
+impl From<FileInfoV0> for FileInfo {
+    fn from(file_info: FileInfoV0) -> Self {
+        Self {
+            raw_size: file_info.raw_size,
+            data_size: file_info.data_size,
+            compression_params: CompressionParams::default().resolve(&file_info.compression),
+            compression: file_info.compression,
+            encryption: file_info.encryption.into(),
+            filename: file_info.filename,
+            metadata: file_info.metadata,
+            data_checksum: file_info.data_checksum,
+            raw_checksum: file_info.raw_checksum,
+            seek_table: None
+        }
+    }
+}
+
+/// Revision 0 encryption type
+///
+/// Predates `Encryption::AESGCM`, so `Unknown` has a different index.
+#[derive(Deserialize)]
+enum EncryptionV0 {
+    None,
+    Aes,
+    Blowfish,
+    Twofish,
+    Unknown
+}
+
+impl From<EncryptionV0> for Encryption {
+    fn from(encryption: EncryptionV0) -> Self {
+        match encryption {
+            EncryptionV0::None => Encryption::None,
+            EncryptionV0::Aes => Encryption::AES,
+            EncryptionV0::Blowfish => Encryption::Blowfish,
+            EncryptionV0::Twofish => Encryption::Twofish,
+            EncryptionV0::Unknown => Encryption::Unknown
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use std::{
-    ops::Range
+use crate::{
+    shared::{
+        key_derivation::KeyDerivation,
+        encryption::Encryption
+    }
 };
 
 use serde::{
@@ -8,23 +11,28 @@ use serde::{
 };
 /// The archive header
 /// 
-/// Contains archive metadata and points to the most up-to-date directory.
-#[derive(Serialize, Deserialize, Debug)]
+/// Contains archive metadata. The directory is located through the trailer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ArchiveHeader {
     /// The library version used to create the archive
     /// 
     /// Format: 16B long array with a UTF-8 encoded version string,
     /// preferably using semantic versioning
     pub version: [u8; 16],
-    /// Byte range that points to the most up-to-date directory
-    pub directory_range: Range<u64>
+    /// How the encryption key is derived from a password, if the archive is password protected
+    pub key_derivation: Option<KeyDerivation>,
+    /// Encryption of the directory and all file headers
+    /// 
+    /// Either `Encryption::None` or `Encryption::AESGCM`.
+    pub index_encryption: Encryption
 }
 
 impl Default for ArchiveHeader {
     fn default() -> Self {
         Self {
             version: crate::get_version_bytes(),
-            directory_range: 0..0
+            key_derivation: None,
+            index_encryption: Encryption::None
         }
     }
 }
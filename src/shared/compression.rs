@@ -4,11 +4,12 @@ use serde::{
 };
 
 /// Compression enum. Describes the compression algorithm used.
-#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, Default)]
 pub enum Compression {
     /// No compression is used
     None,
     /// zstd compression
+    #[default]
     ZStandard,
     /// deflate compression
     Deflate,
@@ -24,28 +25,104 @@ pub enum Compression {
     Unknown
 }
 
+/// Uncompressed byte size of the frames compressed entries are split into by default
+pub const DEFAULT_FRAME_SIZE: u64 = 1024 * 1024;
+
+impl Compression {
+    /// Gets the default compression level or preset of this algorithm
+    /// 
+    /// Returns None if the algorithm has no notion of a level.
+    pub fn default_level(&self) -> Option<u32> {
+        match self {
+            Compression::ZStandard => Some(9),
+            Compression::LZ4 => Some(4),
+            Compression::LZMA | Compression::LZMA2 => Some(6),
+            Compression::Deflate | Compression::BZIP2 => Some(6),
+            _ => None
+        }
+    }
+
+    /// Gets the default uncompressed frame size of this algorithm
+    /// 
+    /// Returns None if the data is stored as a single stream by default. Uncompressed data needs no frames,
+    /// and raw deflate data stays a single stream, so it can be copied into ZIP files as is.
+    pub fn default_frame_size(&self) -> Option<u64> {
+        match self {
+            Compression::None | Compression::Deflate | Compression::Unknown => None,
+            _ => Some(DEFAULT_FRAME_SIZE)
+        }
+    }
+}
+
+/// CompressionParams struct
+/// 
+/// Describes the codec parameters an entry was compressed with.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CompressionParams {
+    /// Compression level or preset. None means the algorithm's default.
+    pub level: Option<u32>,
+    /// zstd window size as a power of two. None means it is derived from the level.
+    pub window_log: Option<u32>,
+    /// Whether zstd long distance matching is enabled
+    pub long_distance_matching: bool
+}
+
+impl CompressionParams {
+    /// ...with a given compression level or preset
+    pub fn with_level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// ...with a given zstd window log
+    pub fn with_window_log(mut self, window_log: u32) -> Self {
+        self.window_log = Some(window_log);
+        self
+    }
+
+    /// ...with zstd long distance matching enabled or disabled
+    pub fn with_long_distance_matching(mut self, long_distance_matching: bool) -> Self {
+        self.long_distance_matching = long_distance_matching;
+        self
+    }
+
+    /// Fills in the defaults of the given algorithm for any unset parameter
+    pub fn resolve(mut self, compression_type: &Compression) -> Self {
+        if self.level.is_none() {
+            self.level = compression_type.default_level();
+        }
+        self
+    }
+}
+
+// The numeric codes follow the declaration order and must never change,
+// as they are part of the file format.
 impl From<u8> for Compression {
     fn from(item: u8) -> Self {
         match item {
             0 => Compression::None,
             1 => Compression::ZStandard,
+            2 => Compression::Deflate,
+            3 => Compression::LZMA,
+            4 => Compression::LZMA2,
+            5 => Compression::BZIP2,
+            6 => Compression::LZ4,
             _ => Compression::Unknown
         }
     }
 }
 
-impl Into<u8> for Compression {
-    fn into(self) -> u8 {
-        match self {
+impl From<Compression> for u8 {
+    fn from(item: Compression) -> Self {
+        match item {
             Compression::None => 0,
             Compression::ZStandard => 1,
+            Compression::Deflate => 2,
+            Compression::LZMA => 3,
+            Compression::LZMA2 => 4,
+            Compression::BZIP2 => 5,
+            Compression::LZ4 => 6,
             _ => 255
         }
     }
-}
-
-impl Default for Compression {
-    fn default() -> Self {
-        Compression::ZStandard
-    }
 }
\ No newline at end of file
@@ -11,7 +11,7 @@ use serde::{
 /// Main directory struct.
 /// 
 /// Maps filenames to a byte range corresponding to the respective file header
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct Directory {
     /// Byte ranges of the file headers by filename
     pub file_headers: HashMap<String, Range<u64>>
@@ -32,12 +32,4 @@ impl Directory {
     pub fn set_file(&mut self, path: &str, header_range: Range<u64>) {
         self.file_headers.insert(String::from(path), header_range);
     }
-}
-
-impl Default for Directory {
-    fn default() -> Self {
-        Self {
-            file_headers: HashMap::new()
-        }
-    }
 }
\ No newline at end of file
@@ -0,0 +1,475 @@
+use crate::{
+    shared::{
+        archive_header::ArchiveHeader,
+        directory::Directory,
+        file_header::FileHeader,
+        file_info::FileInfo,
+        file_metadata::FileMetadata,
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::Encryption,
+        seek_table::SeekTable,
+        key_derivation::{
+            KeyDerivation,
+            SALT_SIZE
+        },
+        trailer::{
+            Trailer,
+            TRAILER_MAGIC
+        }
+    }
+};
+
+use std::{
+    io::{
+        Read,
+        Write,
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
+    },
+    ops::Range,
+    convert::TryInto
+};
+
+use chrono::{
+    DateTime,
+    TimeZone,
+    Utc
+};
+
+/// Encoding into the fixed on-disk layout
+///
+/// All integers are little-endian with explicit widths. See `SPEC.md` for the layout of every type.
+pub trait Encode {
+    /// Encodes self into the given writer
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()>;
+
+    /// Encodes self into a new buffer
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.encode(&mut bytes)
+            .expect("Writing into a Vec can't fail!");
+        bytes
+    }
+}
+
+/// Decoding from the fixed on-disk layout
+///
+/// Fails with `ErrorKind::InvalidData` for values that can't occur in a valid encoding.
+pub trait Decode: Sized {
+    /// Decodes an instance from the given reader
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self>;
+}
+
+/// Creates the error returned for invalid encodings
+fn invalid_data(message: &str) -> IoError {
+    IoError::new(ErrorKind::InvalidData, message)
+}
+
+macro_rules! impl_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl Encode for $integer {
+                fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $integer {
+                fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+                    let mut bytes = [0u8; std::mem::size_of::<$integer>()];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(<$integer>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, i64);
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        (*self as u8).encode(writer)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("Invalid boolean"))
+        }
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        writer.write_all(self)
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let mut bytes = [0u8; N];
+        reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Options are a u8 tag, 0 for None and 1 for Some, followed by the value if there is one
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        match self {
+            Some(value) => {
+                1u8.encode(writer)?;
+                value.encode(writer)
+            },
+            None => 0u8.encode(writer)
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(reader)?)),
+            _ => Err(invalid_data("Invalid option tag"))
+        }
+    }
+}
+
+/// Strings are a u32 byte length followed by the UTF-8 bytes
+impl Encode for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        let length: u32 = self.len().try_into()
+            .map_err(|_| invalid_data("String too long"))?;
+        length.encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let length = u32::decode(reader)? as u64;
+        // Read through take, so a corrupt length can't allocate more than is actually there
+        let mut bytes = vec![];
+        reader.take(length).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != length {
+            return Err(IoError::from(ErrorKind::UnexpectedEof));
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| invalid_data("Invalid UTF-8 string"))
+    }
+}
+
+/// Ranges are the start followed by the end
+impl Encode for Range<u64> {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.start.encode(writer)?;
+        self.end.encode(writer)
+    }
+}
+
+impl Decode for Range<u64> {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let start = u64::decode(reader)?;
+        let end = u64::decode(reader)?;
+        Ok(start..end)
+    }
+}
+
+/// Timestamps are i64 milliseconds since the Unix epoch
+impl Encode for DateTime<Utc> {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.timestamp_millis().encode(writer)
+    }
+}
+
+impl Decode for DateTime<Utc> {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Utc.timestamp_millis_opt(i64::decode(reader)?)
+            .single()
+            .ok_or_else(|| invalid_data("Timestamp out of range"))
+    }
+}
+
+/// Compression is its u8 code
+impl Encode for Compression {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        u8::from(self.clone()).encode(writer)
+    }
+}
+
+impl Decode for Compression {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(Compression::from(u8::decode(reader)?))
+    }
+}
+
+/// Encryption is its u8 code
+impl Encode for Encryption {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        u8::from(self.clone()).encode(writer)
+    }
+}
+
+impl Decode for Encryption {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(Encryption::from(u8::decode(reader)?))
+    }
+}
+
+impl Encode for CompressionParams {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.level.encode(writer)?;
+        self.window_log.encode(writer)?;
+        self.long_distance_matching.encode(writer)
+    }
+}
+
+impl Decode for CompressionParams {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(
+            Self {
+                level: Decode::decode(reader)?,
+                window_log: Decode::decode(reader)?,
+                long_distance_matching: Decode::decode(reader)?
+            }
+        )
+    }
+}
+
+/// Key derivations are a u8 tag, 0 for scrypt and 1 for PBKDF2, followed by the parameters
+impl Encode for KeyDerivation {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        match self {
+            KeyDerivation::Scrypt { salt, log_n, r, p } => {
+                0u8.encode(writer)?;
+                salt.encode(writer)?;
+                log_n.encode(writer)?;
+                r.encode(writer)?;
+                p.encode(writer)
+            },
+            KeyDerivation::PBKDF2 { salt, iterations } => {
+                1u8.encode(writer)?;
+                salt.encode(writer)?;
+                iterations.encode(writer)
+            }
+        }
+    }
+}
+
+impl Decode for KeyDerivation {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        match u8::decode(reader)? {
+            0 => Ok(
+                KeyDerivation::Scrypt {
+                    salt: <[u8; SALT_SIZE]>::decode(reader)?,
+                    log_n: Decode::decode(reader)?,
+                    r: Decode::decode(reader)?,
+                    p: Decode::decode(reader)?
+                }
+            ),
+            1 => Ok(
+                KeyDerivation::PBKDF2 {
+                    salt: <[u8; SALT_SIZE]>::decode(reader)?,
+                    iterations: Decode::decode(reader)?
+                }
+            ),
+            _ => Err(invalid_data("Invalid key derivation tag"))
+        }
+    }
+}
+
+impl Encode for ArchiveHeader {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.version.encode(writer)?;
+        self.key_derivation.encode(writer)?;
+        self.index_encryption.encode(writer)
+    }
+}
+
+impl Decode for ArchiveHeader {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(
+            Self {
+                version: Decode::decode(reader)?,
+                key_derivation: Decode::decode(reader)?,
+                index_encryption: Decode::decode(reader)?
+            }
+        )
+    }
+}
+
+/// Trailers are the directory range followed by the trailer magic bytes
+impl Encode for Trailer {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.directory_range.encode(writer)?;
+        TRAILER_MAGIC.encode(writer)
+    }
+}
+
+impl Decode for Trailer {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let directory_range = Range::<u64>::decode(reader)?;
+        if <[u8; 4]>::decode(reader)? != TRAILER_MAGIC {
+            return Err(invalid_data("Incorrect trailer magic bytes"));
+        }
+        Ok(Trailer::new(directory_range))
+    }
+}
+
+/// Directories are a u32 entry count followed by the entries, sorted by path
+///
+/// Each entry is the path followed by the byte range of its file header.
+impl Encode for Directory {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        let count: u32 = self.file_headers.len().try_into()
+            .map_err(|_| invalid_data("Too many entries"))?;
+        count.encode(writer)?;
+        let mut entries: Vec<(&String, &Range<u64>)> = self.file_headers.iter().collect();
+        entries.sort_by_key(|(path, _)| *path);
+        for (path, range) in entries {
+            path.encode(writer)?;
+            range.encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for Directory {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let count = u32::decode(reader)?;
+        let mut directory = Directory::default();
+        for _ in 0..count {
+            let path = String::decode(reader)?;
+            let range = Range::<u64>::decode(reader)?;
+            if directory.file_headers.insert(path, range).is_some() {
+                return Err(invalid_data("Duplicate path"));
+            }
+        }
+        Ok(directory)
+    }
+}
+
+impl Encode for FileMetadata {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.readonly.encode(writer)?;
+        self.permissions_opt.encode(writer)?;
+        self.added_at.encode(writer)?;
+        self.modified_at.encode(writer)?;
+        self.created_at.encode(writer)?;
+        self.accessed_at.encode(writer)
+    }
+}
+
+impl Decode for FileMetadata {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(
+            Self {
+                readonly: Decode::decode(reader)?,
+                permissions_opt: Decode::decode(reader)?,
+                added_at: Decode::decode(reader)?,
+                modified_at: Decode::decode(reader)?,
+                created_at: Decode::decode(reader)?,
+                accessed_at: Decode::decode(reader)?
+            }
+        )
+    }
+}
+
+/// The seek table isn't part of the file info's encoding, the file header stores it last
+impl Encode for FileInfo {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.raw_size.encode(writer)?;
+        self.data_size.encode(writer)?;
+        self.compression.encode(writer)?;
+        self.compression_params.encode(writer)?;
+        self.encryption.encode(writer)?;
+        self.filename.encode(writer)?;
+        self.metadata.encode(writer)?;
+        self.data_checksum.encode(writer)?;
+        self.raw_checksum.encode(writer)
+    }
+}
+
+impl Decode for FileInfo {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        Ok(
+            Self {
+                raw_size: Decode::decode(reader)?,
+                data_size: Decode::decode(reader)?,
+                compression: Decode::decode(reader)?,
+                compression_params: Decode::decode(reader)?,
+                encryption: Decode::decode(reader)?,
+                filename: Decode::decode(reader)?,
+                metadata: Decode::decode(reader)?,
+                data_checksum: Decode::decode(reader)?,
+                raw_checksum: Decode::decode(reader)?,
+                seek_table: None
+            }
+        )
+    }
+}
+
+impl Encode for FileHeader {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.data_range.encode(writer)?;
+        self.file_info.encode(writer)?;
+        self.file_info.seek_table.encode(writer)
+    }
+}
+
+impl Decode for FileHeader {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let data_range = Range::<u64>::decode(reader)?;
+        let file_info = FileInfo::decode(reader)?;
+        let seek_table = Option::<SeekTable>::decode(reader)?;
+        Ok(
+            Self {
+                data_range,
+                file_info: FileInfo { seek_table, ..file_info }
+            }
+        )
+    }
+}
+
+impl Encode for SeekTable {
+    fn encode<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.frame_size.encode(writer)?;
+        let count: u32 = self.frame_ends.len().try_into()
+            .map_err(|_| invalid_data("Too many frames"))?;
+        count.encode(writer)?;
+        for frame_end in self.frame_ends.iter() {
+            frame_end.encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for SeekTable {
+    fn decode<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let frame_size = u64::decode(reader)?;
+        if frame_size == 0 {
+            return Err(invalid_data("Invalid frame size"));
+        }
+        let count = u32::decode(reader)?;
+        let mut seek_table = SeekTable::new(frame_size);
+        let mut previous_end = 0;
+        for _ in 0..count {
+            let frame_end = u64::decode(reader)?;
+            if frame_end < previous_end {
+                return Err(invalid_data("Frames out of order"));
+            }
+            seek_table.push_frame(frame_end);
+            previous_end = frame_end;
+        }
+        Ok(seek_table)
+    }
+}
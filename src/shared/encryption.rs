@@ -3,12 +3,46 @@ use serde::{
     Deserialize
 };
 
+use crypto::{
+    aes::KeySize,
+    aes_gcm::AesGcm,
+    aessafe::AesSafe256Encryptor,
+    blockmodes::CtrMode
+};
+use blake2::{
+    Blake2s,
+    crypto_mac::Mac
+};
+use rand::{
+    rngs::OsRng,
+    RngCore
+};
+
+/// An encryption key
+/// 
+/// All supported ciphers use 256 bit keys.
+pub type Key = [u8; 32];
+
+/// Byte size of the random IV in front of AES encrypted data
+pub const AES_IV_SIZE: usize = 16;
+/// Byte size of the key check value following the IV
+pub const KEY_CHECK_SIZE: usize = 16;
+/// Byte size of the random nonce prefix in front of AES-GCM encrypted data
+pub const GCM_NONCE_PREFIX_SIZE: usize = 8;
+/// Byte size of the authentication tag following every AES-GCM chunk
+pub const GCM_TAG_SIZE: usize = 16;
+/// Default plaintext size of AES-GCM chunks
+pub const GCM_CHUNK_SIZE: u32 = 64 * 1024;
+/// Maximum plaintext size of AES-GCM chunks accepted when reading
+pub const GCM_MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
 /// Encryption type enum
 /// 
 /// Represents the different available Encryption types.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, Default)]
 pub enum Encryption {
     /// No encryption
+    #[default]
     None,
     /// AES
     AES,
@@ -16,10 +50,21 @@ pub enum Encryption {
     Blowfish,
     /// Twofish
     Twofish,
+    /// AES-256 in GCM mode
+    /// 
+    /// Authenticated encryption, in fixed-size chunks with a tag each.
+    AESGCM,
     /// Unknown/Unsupported
     Unknown,
 }
 
+impl Encryption {
+    /// Returns a boolean indicating whether or not this library can encrypt and decrypt with it
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Encryption::None | Encryption::AES | Encryption::AESGCM)
+    }
+}
+
 impl From<u8> for Encryption {
     fn from(data: u8) -> Self {
         match data {
@@ -27,13 +72,58 @@ impl From<u8> for Encryption {
             1 => Encryption::AES,
             2 => Encryption::Blowfish,
             3 => Encryption::Twofish,
+            4 => Encryption::AESGCM,
             _ => Encryption::Unknown
         }
     }
 }
 
-impl Default for Encryption {
-    fn default() -> Self {
-        Encryption::None
+impl From<Encryption> for u8 {
+    fn from(item: Encryption) -> Self {
+        match item {
+            Encryption::None => 0,
+            Encryption::AES => 1,
+            Encryption::Blowfish => 2,
+            Encryption::Twofish => 3,
+            Encryption::AESGCM => 4,
+            Encryption::Unknown => 255
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Creates an AES-256 cipher in CTR mode, starting at the given IV
+pub fn aes_ctr(key: &Key, iv: &[u8; AES_IV_SIZE]) -> CtrMode<AesSafe256Encryptor> {
+    CtrMode::new(AesSafe256Encryptor::new(key), iv.to_vec())
+}
+
+/// Computes the key check value for a given key and IV/nonce
+/// 
+/// The check value is a keyed BLAKE2S hash of the IV. It is stored next to the IV,
+/// so a wrong key can be detected before any data gets decrypted.
+pub fn key_check(key: &Key, iv: &[u8]) -> [u8; KEY_CHECK_SIZE] {
+    let mut mac = Blake2s::new_varkey(key).expect("Invalid key length!");
+    mac.input(iv);
+    let mut ret = [0u8; KEY_CHECK_SIZE];
+    ret.copy_from_slice(&mac.result().code()[0..KEY_CHECK_SIZE]);
+    ret
+}
+
+/// Creates an AES-256-GCM cipher for a single chunk
+/// 
+/// The nonce consists of the per-file prefix and the chunk index. The chunk size and
+/// whether or not this is the last chunk are authenticated as well, so chunks cant be
+/// reordered, dropped or appended without failing authentication.
+pub fn aes_gcm(key: &Key, nonce_prefix: &[u8; GCM_NONCE_PREFIX_SIZE], chunk_index: u32, chunk_size: u32, is_last: bool) -> AesGcm<'static> {
+    let mut nonce = [0u8; 12];
+    nonce[0..GCM_NONCE_PREFIX_SIZE].copy_from_slice(nonce_prefix);
+    nonce[GCM_NONCE_PREFIX_SIZE..].copy_from_slice(&chunk_index.to_be_bytes());
+    let mut aad = [0u8; 5];
+    aad[0..4].copy_from_slice(&chunk_size.to_le_bytes());
+    aad[4] = is_last as u8;
+    AesGcm::new(KeySize::KeySize256, key, &nonce, &aad)
+}
+
+/// Fills the given buffer with cryptographically secure random bytes
+pub fn random_bytes(buffer: &mut [u8]) {
+    OsRng.fill_bytes(buffer);
+}
@@ -1,59 +1,259 @@
+use crate::{
+    shared::{
+        compression::Compression,
+        encryption::Encryption,
+        format_version::FormatVersion
+    }
+};
+
 use std::{
     fmt::{
         Display,
         Formatter,
         Result as FmtResult
     },
+    io::{
+        Error as IoError,
+        ErrorKind
+    },
+    ops::Range,
     result::Result as StdResult,
     error::Error as ErrorTrait
 };
 
+/// The underlying cause of an error, e.g. an I/O or (de-)serialization error
+pub type Source = Box<dyn ErrorTrait + Send + Sync>;
+
 /// Library-wide error type
 #[derive(Debug)]
 pub enum Error {
-    /// And unknown error has occured
-    Unknown,
+    /// An I/O error without any further context
+    Io(IoError),
     /// Cant read the magic bytes
-    CantReadMagicBytes,
+    CantReadMagicBytes(IoError),
     /// Cant write the magic bytes
-    CantWriteMagicBytes,
+    CantWriteMagicBytes(IoError),
     /// Incorrect first 3 bytes
     IncorrectMagicBytes([u8; 3]),
-    /// Incorrect version
-    VersionMismatch((u8, u8, u8), (u8, u8, u8)),
+    /// The archive's format version isnt supported
+    VersionMismatch {
+        /// The format version of the archive
+        found: FormatVersion,
+        /// The format version supported by this library
+        supported: FormatVersion
+    },
     /// Cant read header
-    CantReadHeader,
+    CantReadHeader(IoError),
     /// Cant write header
-    CantWriteHeader,
+    CantWriteHeader(Source),
     /// Corrupt archive header
-    CorruptHeader,
+    CorruptHeader(Source),
+    /// Cant read the trailer at the end of the archive
+    CantReadTrailer(IoError),
+    /// Cant write the trailer at the end of the archive
+    CantWriteTrailer(IoError),
+    /// Corrupt trailer
+    CorruptTrailer(Source),
+    /// Corrupt entry frame, found while reading sequentially
+    CorruptEntryFrame {
+        /// Byte offset of the entry frame
+        offset: u64,
+        /// The underlying cause
+        source: Source
+    },
+    /// The archive's format version can't be read sequentially
+    NotStreamable(FormatVersion),
     /// Corrupt file header
-    CorruptFileHeader,
+    CorruptFileHeader {
+        /// Path of the entry
+        path: String,
+        /// Byte offset of the file header
+        offset: u64,
+        /// The underlying cause
+        source: Source
+    },
     /// Corrupt directory
-    CorruptDirectory,
+    CorruptDirectory {
+        /// Byte offset of the directory
+        offset: u64,
+        /// The underlying cause
+        source: Source
+    },
     /// Couldnt read directory
-    CantReadDirectory,
+    CantReadDirectory {
+        /// Byte offset of the directory
+        offset: u64,
+        /// The underlying cause
+        source: IoError
+    },
     /// Couldnt write directory
-    CantWriteDirectory,
+    CantWriteDirectory {
+        /// Byte offset of the directory
+        offset: u64,
+        /// The underlying cause
+        source: Source
+    },
     /// Couldnt read file
-    CantReadFile,
+    CantReadFile {
+        /// Path of the entry
+        path: String,
+        /// Byte offset of the data
+        offset: u64,
+        /// The underlying cause
+        source: IoError
+    },
     /// Couldnt write file
-    CantWriteFile,
+    CantWriteFile {
+        /// Path of the entry
+        path: String,
+        /// The underlying cause
+        source: IoError
+    },
     /// Couldnt read file header
-    CantReadFileHeader,
+    CantReadFileHeader {
+        /// Path of the entry
+        path: String,
+        /// Byte offset of the file header
+        offset: u64,
+        /// The underlying cause
+        source: IoError
+    },
     /// Couldnt write file header
-    CantWriteFileHeader,
+    CantWriteFileHeader {
+        /// Path of the entry
+        path: String,
+        /// The underlying cause
+        source: IoError
+    },
+    /// The operation is only allowed before any file has been written
+    FilesAlreadyWritten,
     /// Couldnt find the file specified
-    FileNotFound(String)
+    FileNotFound(String),
+    /// The requested byte range doesnt lie within the file
+    InvalidRange {
+        /// Path of the entry
+        path: String,
+        /// The requested byte range
+        range: Range<u64>,
+        /// Size of the uncompressed/decrypted data
+        raw_size: u64
+    },
+    /// The file is compressed or encrypted, so its data cant be borrowed as stored
+    NotStored(String),
+    /// The checksum of the compressed/encrypted data doesnt match
+    DataChecksumMismatch(String),
+    /// The checksum of the uncompressed/decrypted data doesnt match
+    RawChecksumMismatch(String),
+    /// A key is required, but none was given
+    KeyRequired,
+    /// The given key doesnt match the one used for encryption
+    WrongKey,
+    /// Authenticated data has been tampered with or truncated
+    AuthenticationFailed,
+    /// The encryption type isnt supported
+    UnsupportedEncryption(Encryption),
+    /// The compression type isnt supported
+    UnsupportedCompression(Compression),
+    /// The archive isnt password protected
+    NotPasswordProtected,
+    /// The key derivation parameters are invalid
+    InvalidKeyDerivation
+}
+
+impl Error {
+    /// Gives a context-less I/O error its context
+    /// 
+    /// Any other error is returned unchanged.
+    pub(crate) fn map_io<F: FnOnce(IoError) -> Error>(self, context: F) -> Self {
+        match self {
+            Error::Io(source) => context(source),
+            other => other
+        }
+    }
+
+    /// Turns the error into an I/O error, e.g. for returning it from within `Read` or `Write`
+    /// 
+    /// Context-less I/O errors are unwrapped, any other error becomes invalid data.
+    pub(crate) fn into_io(self) -> IoError {
+        match self {
+            Error::Io(source) => source,
+            other => IoError::new(ErrorKind::InvalidData, other)
+        }
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(source: IoError) -> Self {
+        Error::Io(source)
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{:?}", self)
+        match self {
+            Error::Io(source) => write!(f, "I/O error: {}", source),
+            Error::CantReadMagicBytes(source) => write!(f, "can't read the magic bytes: {}", source),
+            Error::CantWriteMagicBytes(source) => write!(f, "can't write the magic bytes: {}", source),
+            Error::IncorrectMagicBytes(bytes) => write!(f, "not a .var archive, found magic bytes {:?}", bytes),
+            Error::VersionMismatch { found, supported } => write!(f, "unsupported archive format version {}, the newest supported is {}", found, supported),
+            Error::CantReadHeader(source) => write!(f, "can't read the archive header: {}", source),
+            Error::CantWriteHeader(source) => write!(f, "can't write the archive header: {}", source),
+            Error::CorruptHeader(source) => write!(f, "corrupt archive header: {}", source),
+            Error::CantReadTrailer(source) => write!(f, "can't read the archive trailer: {}", source),
+            Error::CantWriteTrailer(source) => write!(f, "can't write the archive trailer: {}", source),
+            Error::CorruptTrailer(source) => write!(f, "corrupt archive trailer: {}", source),
+            Error::CorruptEntryFrame { offset, source } => write!(f, "corrupt entry frame at byte {}: {}", offset, source),
+            Error::NotStreamable(version) => write!(f, "archives of format version {} can't be read sequentially", version),
+            Error::CorruptFileHeader { path, offset, source } => write!(f, "corrupt file header of '{}' at byte {}: {}", path, offset, source),
+            Error::CorruptDirectory { offset, source } => write!(f, "corrupt directory at byte {}: {}", offset, source),
+            Error::CantReadDirectory { offset, source } => write!(f, "can't read the directory at byte {}: {}", offset, source),
+            Error::CantWriteDirectory { offset, source } => write!(f, "can't write the directory at byte {}: {}", offset, source),
+            Error::CantReadFile { path, offset, source } => write!(f, "can't read the data of '{}' at byte {}: {}", path, offset, source),
+            Error::CantWriteFile { path, source } => write!(f, "can't write the data of '{}': {}", path, source),
+            Error::CantReadFileHeader { path, offset, source } => write!(f, "can't read the file header of '{}' at byte {}: {}", path, offset, source),
+            Error::CantWriteFileHeader { path, source } => write!(f, "can't write the file header of '{}': {}", path, source),
+            Error::FilesAlreadyWritten => write!(f, "the operation is only allowed before any file has been written"),
+            Error::FileNotFound(path) => write!(f, "file '{}' not found", path),
+            Error::InvalidRange { path, range, raw_size } => write!(f, "byte range {:?} is out of bounds for '{}' of {} bytes", range, path, raw_size),
+            Error::NotStored(path) => write!(f, "'{}' is compressed or encrypted, so its data can't be borrowed", path),
+            Error::DataChecksumMismatch(path) => write!(f, "checksum mismatch in the stored data of '{}'", path),
+            Error::RawChecksumMismatch(path) => write!(f, "checksum mismatch in the decompressed data of '{}'", path),
+            Error::KeyRequired => write!(f, "a key is required, but none was given"),
+            Error::WrongKey => write!(f, "wrong key"),
+            Error::AuthenticationFailed => write!(f, "authentication failed, the data has been tampered with or truncated"),
+            Error::UnsupportedEncryption(encryption) => write!(f, "unsupported encryption {:?}", encryption),
+            Error::UnsupportedCompression(compression) => write!(f, "unsupported compression {:?}", compression),
+            Error::NotPasswordProtected => write!(f, "the archive isn't password protected"),
+            Error::InvalidKeyDerivation => write!(f, "invalid key derivation parameters")
+        }
     }
 }
 
-impl ErrorTrait for Error { }
+impl ErrorTrait for Error {
+    fn source(&self) -> Option<&(dyn ErrorTrait + 'static)> {
+        match self {
+            Error::Io(source)
+            | Error::CantReadMagicBytes(source)
+            | Error::CantWriteMagicBytes(source)
+            | Error::CantReadHeader(source)
+            | Error::CantReadTrailer(source)
+            | Error::CantWriteTrailer(source)
+            | Error::CantReadDirectory { source, .. }
+            | Error::CantReadFile { source, .. }
+            | Error::CantWriteFile { source, .. }
+            | Error::CantReadFileHeader { source, .. }
+            | Error::CantWriteFileHeader { source, .. } => Some(source),
+            Error::CantWriteHeader(source)
+            | Error::CorruptHeader(source)
+            | Error::CorruptTrailer(source)
+            | Error::CorruptFileHeader { source, .. }
+            | Error::CorruptEntryFrame { source, .. }
+            | Error::CorruptDirectory { source, .. }
+            | Error::CantWriteDirectory { source, .. } => Some(source.as_ref()),
+            _ => None
+        }
+    }
+}
 
 /// Library-wide result type
-pub type Result<T> = StdResult<T, Error>;
\ No newline at end of file
+pub type Result<T> = StdResult<T, Error>;
@@ -1,8 +1,12 @@
 use crate::{
     shared::{
         file_metadata::FileMetadata,
-        compression::Compression,
-        encryption::Encryption
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::Encryption,
+        seek_table::SeekTable
     }
 };
 
@@ -30,6 +34,8 @@ pub struct FileInfo {
     pub data_size: u64,
     /// The compression algorithm used
     pub compression: Compression,
+    /// The compression parameters used
+    pub compression_params: CompressionParams,
     /// The encryption algorithm used
     pub encryption: Encryption,
     /// The filename
@@ -39,7 +45,10 @@ pub struct FileInfo {
     /// Checksum of the compressed/encrypted data
     pub data_checksum: [u8; 32],
     /// Checksum of the uncompressed/decrypted data
-    pub raw_checksum: [u8; 32]
+    pub raw_checksum: [u8; 32],
+    /// Seek table, if the data was compressed in independent frames
+    #[serde(skip)]
+    pub seek_table: Option<SeekTable>
 }
 
 impl FileInfo {
@@ -51,18 +60,12 @@ impl FileInfo {
 
     /// Returns a boolean indicating whether or not this file is compressed
     pub fn is_compressed(&self) -> bool {
-        match self.compression {
-            Compression::None => false,
-            _ => true
-        }
+        !matches!(self.compression, Compression::None)
     }
 
     /// Returns a boolean indicating whether or not this file is encrypted
     pub fn is_encrypted(&self) -> bool {
-        match self.encryption {
-            Encryption::None => false,
-            _ => true
-        }
+        !matches!(self.encryption, Encryption::None)
     }
 
     /// With a given raw size
@@ -83,12 +86,30 @@ impl FileInfo {
         self
     }
 
+    /// With given compression parameters
+    pub fn with_compression_params(mut self, compression_params: CompressionParams) -> Self {
+        self.compression_params = compression_params;
+        self
+    }
+
+    /// With a given encryption type
+    pub fn with_encryption(mut self, encryption_type: Encryption) -> Self {
+        self.encryption = encryption_type;
+        self
+    }
+
     /// With a given data checksum
     pub fn with_data_checksum(mut self, data_checksum: [u8; 32]) -> Self {
         self.data_checksum = data_checksum;
         self
     }
 
+    /// With a given seek table
+    pub fn with_seek_table(mut self, seek_table: SeekTable) -> Self {
+        self.seek_table = Some(seek_table);
+        self
+    }
+
     /// With a given raw checksum
     pub fn with_raw_checksum(mut self, raw_checksum: [u8; 32]) -> Self {
         self.raw_checksum = raw_checksum;
@@ -100,17 +121,18 @@ impl Display for FileInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         writeln!(f, "FileInfo {{")?;
         writeln!(f, "    compression: {:?}", self.compression)?;
+        writeln!(f, "    compression_params: {:?}", self.compression_params)?;
         writeln!(f, "    encryption: {:?}", self.encryption)?;
         write!(f, "    data_checksum: ")?;
         for byte in self.data_checksum.iter() {
             write!(f, "{:x}", byte)?;
         }
-        writeln!(f, "")?;
+        writeln!(f)?;
         write!(f, "    raw_checksum: ")?;
         for byte in self.raw_checksum.iter() {
             write!(f, "{:x}", byte)?;
         }
-        writeln!(f, "")?;
+        writeln!(f)?;
         writeln!(f, "}}")
     }
 }
@@ -121,11 +143,13 @@ impl Default for FileInfo {
             data_checksum: [0; 32],
             raw_checksum: [0; 32],
             compression: Compression::None,
+            compression_params: CompressionParams::default(),
             encryption: Encryption::None,
             raw_size: 0,
             data_size: 0,
             filename: String::new(),
-            metadata: FileMetadata::default()
+            metadata: FileMetadata::default(),
+            seek_table: None
         }
     }
 }
\ No newline at end of file
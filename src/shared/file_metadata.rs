@@ -83,11 +83,11 @@ impl From<&Metadata> for FileMetadata {
         
         let added_at = Utc::now();
         Self {
-            added_at: added_at,
-            created_at: created_at,
-            modified_at: modified_at,
-            accessed_at: accessed_at,
-            readonly: readonly,
+            added_at,
+            created_at,
+            modified_at,
+            accessed_at,
+            readonly,
             permissions_opt: mode_opt
         }
     }
@@ -0,0 +1,84 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult
+    }
+};
+
+/// Byte size of the encoded format version
+pub const FORMAT_VERSION_SIZE: usize = 4;
+
+/// The on-disk format version
+///
+/// Stored right after the magic bytes as two little-endian u16s, independent of the library version.
+/// A new minor version may only add data, so its readers can still read every older minor version.
+/// Any other change to the layout requires a new major version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FormatVersion {
+    /// Major version. Archives with a different major version can't be read.
+    pub major: u16,
+    /// Minor version
+    pub minor: u16
+}
+
+impl FormatVersion {
+    /// The format version written by this library
+    pub const CURRENT: FormatVersion = FormatVersion { major: 4, minor: 0 };
+
+    /// Archives written before the format version was stored
+    ///
+    /// Their layout is decoded by `read::versioned`.
+    pub const REVISION_0: FormatVersion = FormatVersion { major: 0, minor: 0 };
+
+    /// Creates a new format version
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self {
+            major,
+            minor
+        }
+    }
+
+    /// Decodes the format version from its on-disk representation
+    pub fn from_bytes(bytes: [u8; FORMAT_VERSION_SIZE]) -> Self {
+        Self {
+            major: u16::from_le_bytes([bytes[0], bytes[1]]),
+            minor: u16::from_le_bytes([bytes[2], bytes[3]])
+        }
+    }
+
+    /// Returns a boolean indicating whether or not archives of this version can be read sequentially
+    /// 
+    /// Entries of revision 0 archives have neither a frame nor their file header in front of their data.
+    pub fn is_streamable(&self) -> bool {
+        *self != Self::REVISION_0
+    }
+
+    /// Encodes the format version into its on-disk representation
+    pub fn to_bytes(self) -> [u8; FORMAT_VERSION_SIZE] {
+        let major = self.major.to_le_bytes();
+        let minor = self.minor.to_le_bytes();
+        [major[0], major[1], minor[0], minor[1]]
+    }
+
+    /// Returns a boolean indicating whether or not archives of this version can be read
+    ///
+    /// Revision 0 and every minor version of the current major version up to the current one are supported.
+    /// Newer minor versions are rejected as well: the archive header, directory and file headers aren't length-prefixed,
+    /// so data a newer minor version adds to them can't be skipped.
+    pub fn is_supported(&self) -> bool {
+        (self.major == Self::CURRENT.major && *self <= Self::CURRENT) || *self == Self::REVISION_0
+    }
+}
+
+impl Default for FormatVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl Display for FormatVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
@@ -0,0 +1,13 @@
+/// Byte size of a marker
+pub const MARKER_SIZE: usize = 3;
+
+/// Marker in front of every entry
+/// 
+/// Followed by the size of the file header (u64), the file header and the entry's data.
+pub const ENTRY_MARKER: [u8; MARKER_SIZE] = *b"VLE";
+
+/// Marker in front of the directory, ending the sequence of entries
+pub const DIRECTORY_MARKER: [u8; MARKER_SIZE] = *b"VDR";
+
+/// Byte size of an entry frame, in front of the file header
+pub const ENTRY_FRAME_SIZE: u64 = MARKER_SIZE as u64 + 8;
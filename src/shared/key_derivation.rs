@@ -0,0 +1,126 @@
+use crate::{
+    shared::{
+        encryption::{
+            self,
+            Key
+        },
+        error::{
+            Error,
+            Result
+        }
+    }
+};
+
+use serde::{
+    Serialize,
+    Deserialize
+};
+use crypto::{
+    scrypt::{
+        self,
+        ScryptParams
+    },
+    pbkdf2,
+    hmac::Hmac,
+    sha2::Sha256
+};
+
+/// Byte size of the per-archive salt
+pub const SALT_SIZE: usize = 16;
+/// Maximum number of bytes scrypt may use, 1GiB
+pub const SCRYPT_MAX_MEMORY: u64 = 1 << 30;
+/// Maximum number of PBKDF2 iterations
+pub const PBKDF2_MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Key derivation enum
+/// 
+/// Describes how the encryption key is derived from a password.
+/// The parameters are stored in the archive header, so the work factor
+/// can be raised for new archives without breaking older ones.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum KeyDerivation {
+    /// scrypt
+    Scrypt {
+        /// Per-archive salt
+        salt: [u8; SALT_SIZE],
+        /// The log2 of the CPU/memory cost parameter N
+        log_n: u8,
+        /// The block size parameter
+        r: u32,
+        /// The parallelization parameter
+        p: u32
+    },
+    /// PBKDF2, using HMAC-SHA256
+    PBKDF2 {
+        /// Per-archive salt
+        salt: [u8; SALT_SIZE],
+        /// Number of iterations
+        iterations: u32
+    }
+}
+
+impl KeyDerivation {
+    /// Creates scrypt parameters with a random salt
+    pub fn scrypt(log_n: u8, r: u32, p: u32) -> Self {
+        KeyDerivation::Scrypt {
+            salt: random_salt(),
+            log_n,
+            r,
+            p
+        }
+    }
+
+    /// Creates PBKDF2 parameters with a random salt
+    pub fn pbkdf2(iterations: u32) -> Self {
+        KeyDerivation::PBKDF2 {
+            salt: random_salt(),
+            iterations
+        }
+    }
+
+    /// Derives the encryption key from a given password
+    /// 
+    /// Fails if the parameters are out of range, e.g. because they were read from a corrupt header.
+    pub fn derive_key(&self, password: &str) -> Result<Key> {
+        let mut key: Key = [0; 32];
+        match self {
+            KeyDerivation::Scrypt { salt, log_n, r, p } => {
+                // scrypt requires N < 2^(16 * r) and r * p < 2^30
+                if *log_n == 0 || *r == 0 || *p == 0 || *log_n as u64 >= 16 * *r as u64 || (*r as u64) * (*p as u64) >= 1 << 30 {
+                    return Err(Error::InvalidKeyDerivation);
+                }
+                // Keep memory usage (128 * r * 2^log_n bytes for the scratch pad, 128 * r * p bytes for the blocks) sane
+                let memory_opt = 1u64.checked_shl(*log_n as u32)
+                    .and_then(|n| n.checked_add(*p as u64))
+                    .and_then(|blocks| blocks.checked_mul(128 * *r as u64));
+                if !matches!(memory_opt, Some(memory) if memory <= SCRYPT_MAX_MEMORY) {
+                    return Err(Error::InvalidKeyDerivation);
+                }
+                let params = ScryptParams::new(*log_n, *r, *p);
+                scrypt::scrypt(password.as_bytes(), salt, &params, &mut key);
+            },
+            KeyDerivation::PBKDF2 { salt, iterations } => {
+                if *iterations == 0 || *iterations > PBKDF2_MAX_ITERATIONS {
+                    return Err(Error::InvalidKeyDerivation);
+                }
+                let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+                pbkdf2::pbkdf2(&mut mac, salt, *iterations, &mut key);
+            }
+        }
+        Ok(key)
+    }
+}
+
+impl Default for KeyDerivation {
+    /// scrypt with N = 2^15, r = 8 and p = 1, using 32MiB of memory
+    fn default() -> Self {
+        KeyDerivation::scrypt(15, 8, 1)
+    }
+}
+
+/// Creates a new random salt
+fn random_salt() -> [u8; SALT_SIZE] {
+    let mut salt = [0u8; SALT_SIZE];
+    encryption::random_bytes(&mut salt);
+    salt
+}
@@ -8,9 +8,21 @@ pub mod compression;
 pub mod encryption;
 /// Archive header
 pub mod archive_header;
+/// Archive trailer & directory pointer
+pub mod trailer;
+/// Entry & directory markers for sequential reading
+pub mod frame;
+/// On-disk format version
+pub mod format_version;
+/// Fixed little-endian on-disk encoding
+pub mod encoding;
 /// Shared error handling
 pub mod error;
 /// File metadata
 pub mod file_metadata;
 /// File info struct
-pub mod file_info;
\ No newline at end of file
+pub mod file_info;
+/// Seek tables of entries compressed in independent frames
+pub mod seek_table;
+/// Password based key derivation
+pub mod key_derivation;
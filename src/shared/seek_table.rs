@@ -0,0 +1,54 @@
+use std::{
+    ops::Range
+};
+
+/// The seek table of an entry that was compressed in independent frames
+/// 
+/// Every frame holds `frame_size` bytes of uncompressed data, except the last one,
+/// which holds the rest. Since every frame can be decompressed on its own,
+/// seeking only ever has to decompress a single frame.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SeekTable {
+    /// Byte size of the uncompressed data of each frame
+    pub frame_size: u64,
+    /// End offset of each frame within the compressed data, before encryption
+    pub frame_ends: Vec<u64>
+}
+
+impl SeekTable {
+    /// Creates a new, empty seek table for frames of the given size
+    pub fn new(frame_size: u64) -> Self {
+        Self {
+            frame_size,
+            frame_ends: vec![]
+        }
+    }
+
+    /// Gets the number of frames
+    pub fn get_frame_count(&self) -> usize {
+        self.frame_ends.len()
+    }
+
+    /// Gets the byte range of a frame within the compressed data
+    pub fn get_frame_range(&self, frame_index: usize) -> Option<Range<u64>> {
+        let end = *self.frame_ends.get(frame_index)?;
+        let begin = match frame_index {
+            0 => 0,
+            _ => self.frame_ends[frame_index - 1]
+        };
+        Some(begin..end)
+    }
+
+    /// Gets the index of the frame holding the given offset of the uncompressed data
+    /// 
+    /// Offsets past the last frame map to the last frame.
+    pub fn get_frame_index(&self, raw_offset: u64) -> usize {
+        let frame_index = (raw_offset / self.frame_size.max(1)) as usize;
+        frame_index.min(self.get_frame_count().saturating_sub(1))
+    }
+
+    /// Appends a frame ending at the given offset of the compressed data
+    pub fn push_frame(&mut self, frame_end: u64) {
+        self.frame_ends.push(frame_end);
+    }
+}
@@ -0,0 +1,28 @@
+use std::{
+    ops::Range
+};
+
+/// Byte size of the encoded trailer
+pub const TRAILER_SIZE: u64 = 20;
+
+/// Magic bytes closing the trailer
+pub const TRAILER_MAGIC: [u8; 4] = *b"VEND";
+
+/// The archive trailer
+/// 
+/// Fixed-size and written after the directory, at the very end of the archive,
+/// so an archive can be written front to back without ever seeking.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Trailer {
+    /// Byte range of the directory
+    pub directory_range: Range<u64>
+}
+
+impl Trailer {
+    /// Creates a new trailer pointing to the given directory range
+    pub fn new(directory_range: Range<u64>) -> Self {
+        Self {
+            directory_range
+        }
+    }
+}
@@ -1,51 +1,141 @@
 use crate::{
     shared::{
-        compression::Compression
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::Encryption,
+        error::{
+            Error,
+            Result
+        }
     },
     write::{
-        hash_writer::HashWriter
+        crypt_writer::CryptWriter
     }
 };
 
 use std::{
     io::{
         Write,
-        Seek,
-        SeekFrom,
+        Error as IoError,
+        ErrorKind,
         Result as IoResult
     }
 };
 
-use zstd::{
-    Encoder
+use zstd::stream::{
+    zio,
+    raw::{
+        self,
+        CParameter
+    }
+};
+
+use xz2::{
+    write::XzEncoder,
+    stream::{
+        Stream,
+        LzmaOptions
+    }
 };
 
+use flate2::write::DeflateEncoder;
+
+use bzip2::write::BzEncoder;
+
+/// Highest LZMA/LZMA2 preset
+const LZMA_MAX_PRESET: u32 = 9;
+
 /// CompWriter enum
 /// 
 /// Wraps a writer in one of the supported compression algorithms.
-pub enum CompWriter<'w, W: Write + Seek + 'w> {
+pub enum CompWriter<W: Write> {
     /// None - misconfigured Writer.
     None,
     /// Raw - no compression.
-    Raw(HashWriter<'w, W>),
+    Raw(CryptWriter<W>),
     /// Zstd compression.
-    ZStandard(Encoder<HashWriter<'w, W>>)
+    ZStandard(zio::Writer<CryptWriter<W>, raw::Encoder>),
+    /// LZ4 compression. Very fast decompression.
+    LZ4(lz4::Encoder<CryptWriter<W>>),
+    /// LZMA compression, using the legacy .lzma container.
+    LZMA(XzEncoder<CryptWriter<W>>),
+    /// LZMA2 compression, using the .xz container.
+    LZMA2(XzEncoder<CryptWriter<W>>),
+    /// Raw deflate compression, as used within ZIP archives.
+    Deflate(DeflateEncoder<CryptWriter<W>>),
+    /// BZIP2 compression.
+    BZIP2(BzEncoder<CryptWriter<W>>)
 }
 
-impl<'w, W: Write + Seek + 'w> CompWriter<'w, W> {
+impl<W: Write> CompWriter<W> {
     /// Creates a new CompWriter
     /// 
-    /// Wraps a given writer first in a HashWriter and then in a CompWriter variant
-    /// corresponding to the chosen compression algorithm.
-    pub fn new(writer: &'w mut W, compression_type: Compression) -> Self {
-        match compression_type {
-            Compression::None => CompWriter::Raw(HashWriter::new(writer)),
+    /// Wraps a given CryptWriter in a CompWriter variant corresponding
+    /// to the chosen compression algorithm.
+    /// Parameters left unset use the codec's defaults.
+    /// Parameters that don't apply to the chosen codec are ignored.
+    /// With more than one worker, zstd compresses on that many background threads.
+    /// The output remains a regular zstd stream.
+    /// Fails with `Error::UnsupportedCompression` for algorithms that can't be written.
+    pub fn new(writer: CryptWriter<W>, compression_type: Compression, params: &CompressionParams, workers: u32) -> Result<Self> {
+        let level_opt = params.level.or_else(|| compression_type.default_level());
+        let comp_writer = match compression_type {
+            Compression::None => CompWriter::Raw(writer),
             Compression::ZStandard => {
-                let encoder = Encoder::new(HashWriter::new(writer), 9).expect("Error creating Zstd encoder!");
-                CompWriter::ZStandard(encoder)
+                // Parameters zstd rejects, e.g. a window log out of its bounds, are invalid input
+                let invalid_input = |source: IoError| IoError::new(ErrorKind::InvalidInput, source);
+                let mut encoder = raw::Encoder::new(level_opt.unwrap_or_default() as i32)
+                    .map_err(invalid_input)?;
+                if let Some(window_log) = params.window_log {
+                    encoder.set_parameter(CParameter::WindowLog(window_log))
+                        .map_err(invalid_input)?;
+                }
+                if params.long_distance_matching {
+                    encoder.set_parameter(CParameter::EnableLongDistanceMatching(true))
+                        .map_err(invalid_input)?;
+                }
+                if workers > 1 {
+                    encoder.set_parameter(CParameter::NbWorkers(workers))
+                        .map_err(invalid_input)?;
+                }
+                CompWriter::ZStandard(zio::Writer::new(writer, encoder))
             },
-            _ => unimplemented!("Not implemented!")
-        }
+            Compression::LZ4 => {
+                let encoder = lz4::EncoderBuilder::new()
+                    .level(level_opt.unwrap_or_default())
+                    .build(writer)?;
+                CompWriter::LZ4(encoder)
+            },
+            Compression::LZMA => {
+                let preset = lzma_preset(level_opt.unwrap_or_default())?;
+                let options = LzmaOptions::new_preset(preset)
+                    .map_err(IoError::from)?;
+                let stream = Stream::new_lzma_encoder(&options)
+                    .map_err(IoError::from)?;
+                CompWriter::LZMA(XzEncoder::new_stream(writer, stream))
+            },
+            Compression::LZMA2 => {
+                let preset = lzma_preset(level_opt.unwrap_or_default())?;
+                CompWriter::LZMA2(XzEncoder::new(writer, preset))
+            },
+            Compression::Deflate => {
+                let level = flate2::Compression::new(level_opt.unwrap_or_default());
+                CompWriter::Deflate(DeflateEncoder::new(writer, level))
+            },
+            Compression::BZIP2 => {
+                // The bzip2 bindings only offer three levels
+                let level = match level_opt.unwrap_or_default() {
+                    0..=3 => bzip2::Compression::Fastest,
+                    4..=6 => bzip2::Compression::Default,
+                    _ => bzip2::Compression::Best
+                };
+                CompWriter::BZIP2(BzEncoder::new(writer, level))
+            },
+            other => return Err(Error::UnsupportedCompression(other))
+        };
+        Ok(comp_writer)
     }
     
     /// Gets the compression type corresponding to this writer variant
@@ -53,30 +143,84 @@ impl<'w, W: Write + Seek + 'w> CompWriter<'w, W> {
         match self {
             CompWriter::Raw(_) => Compression::None,
             CompWriter::ZStandard(_) => Compression::ZStandard,
+            CompWriter::LZ4(_) => Compression::LZ4,
+            CompWriter::LZMA(_) => Compression::LZMA,
+            CompWriter::LZMA2(_) => Compression::LZMA2,
+            CompWriter::Deflate(_) => Compression::Deflate,
+            CompWriter::BZIP2(_) => Compression::BZIP2,
             _ => Compression::Unknown
         }
     }
+
+    /// Gets the encryption type of the underlying writer
+    pub fn get_encryption_type(&self) -> Encryption {
+        match self {
+            CompWriter::Raw(crypt_writer) => crypt_writer.get_encryption_type(),
+            CompWriter::ZStandard(encoder) => encoder.writer().get_encryption_type(),
+            CompWriter::LZ4(encoder) => encoder.writer().get_encryption_type(),
+            CompWriter::LZMA(encoder) | CompWriter::LZMA2(encoder) => encoder.get_ref().get_encryption_type(),
+            CompWriter::Deflate(encoder) => encoder.get_ref().get_encryption_type(),
+            CompWriter::BZIP2(encoder) => encoder.get_ref().get_encryption_type(),
+            _ => Encryption::Unknown
+        }
+    }
     
     /// Gets the data hash, dropping the internal writer in the process
     /// 
     /// Only use this once you have finished writing.
-    pub fn get_data_hash(&mut self) -> [u8; 32] {
-        let mut hash_writer = match std::mem::replace(self, CompWriter::None) {
-            CompWriter::Raw(hash_writer) => hash_writer,
-            CompWriter::ZStandard(encoder) => encoder.finish().unwrap(),
-            _ => panic!("ERROR")
+    /// Anything written afterwards will be neither compressed nor encrypted.
+    /// If finishing fails, the writer is left misconfigured.
+    pub fn get_data_hash(&mut self) -> IoResult<[u8; 32]> {
+        let crypt_writer = match std::mem::replace(self, CompWriter::None) {
+            CompWriter::Raw(crypt_writer) => crypt_writer,
+            CompWriter::ZStandard(mut encoder) => {
+                encoder.finish()?;
+                encoder.into_inner().0
+            },
+            CompWriter::LZ4(encoder) => {
+                let (crypt_writer, result) = encoder.finish();
+                result?;
+                crypt_writer
+            },
+            CompWriter::LZMA(encoder) | CompWriter::LZMA2(encoder) => encoder.finish()?,
+            CompWriter::Deflate(encoder) => encoder.finish()?,
+            CompWriter::BZIP2(encoder) => encoder.finish()?,
+            CompWriter::None => return Err(IoError::other("Writer misconfigured!"))
         };
+        let mut hash_writer = crypt_writer.finish()?;
         let ret = hash_writer.get_hash();
-        *self = CompWriter::Raw(hash_writer);
-        ret
+        *self = CompWriter::Raw(CryptWriter::Raw(hash_writer));
+        Ok(ret)
+    }
+
+    /// Unwraps the underlying writer
+    /// 
+    /// Only use this after retrieving the data hash.
+    pub fn into_inner(self) -> W {
+        match self {
+            CompWriter::Raw(CryptWriter::Raw(hash_writer)) => hash_writer.into_inner(),
+            _ => panic!("Writer not finished!")
+        }
+    }
+}
+
+/// Checks an LZMA/LZMA2 preset, which ranges from 0 to 9
+fn lzma_preset(preset: u32) -> IoResult<u32> {
+    if preset > LZMA_MAX_PRESET {
+        return Err(IoError::new(ErrorKind::InvalidInput, format!("LZMA presets range from 0 to {}, got {}", LZMA_MAX_PRESET, preset)));
     }
+    Ok(preset)
 }
 
-impl<'w, W: Write + Seek + 'w> Write for CompWriter<'w, W> {
+impl<W: Write> Write for CompWriter<W> {
     fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
         let writer: &mut dyn Write = match self {
-            CompWriter::Raw(hash_writer) => hash_writer,
+            CompWriter::Raw(crypt_writer) => crypt_writer,
             CompWriter::ZStandard(encoder) => encoder,
+            CompWriter::LZ4(encoder) => encoder,
+            CompWriter::LZMA(encoder) | CompWriter::LZMA2(encoder) => encoder,
+            CompWriter::Deflate(encoder) => encoder,
+            CompWriter::BZIP2(encoder) => encoder,
             _ => panic!("Writer misconfigured!")
         };
         writer.write(buf)
@@ -84,21 +228,14 @@ impl<'w, W: Write + Seek + 'w> Write for CompWriter<'w, W> {
     
     fn flush(&mut self) -> IoResult<()> {
         let writer: &mut dyn Write = match self {
-            CompWriter::Raw(hash_writer) => hash_writer,
+            CompWriter::Raw(crypt_writer) => crypt_writer,
             CompWriter::ZStandard(encoder) => encoder,
+            CompWriter::LZ4(encoder) => encoder,
+            CompWriter::LZMA(encoder) | CompWriter::LZMA2(encoder) => encoder,
+            CompWriter::Deflate(encoder) => encoder,
+            CompWriter::BZIP2(encoder) => encoder,
             _ => panic!("Writer misconfigured!")
         };
         writer.flush()
     }
 }
-
-impl<'w, W: Write + Seek + 'w> Seek for CompWriter<'w, W> {
-    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
-        let writer: &mut dyn Seek = match self {
-            CompWriter::Raw(hash_writer) => hash_writer,
-            CompWriter::ZStandard(encoder) => encoder.get_mut(),
-            _ => panic!("Writer misconfigured!")
-        };
-        writer.seek(pos)
-    }
-}
\ No newline at end of file
@@ -0,0 +1,259 @@
+use crate::{
+    shared::{
+        encryption::{
+            self,
+            Encryption,
+            Key,
+            AES_IV_SIZE,
+            GCM_NONCE_PREFIX_SIZE,
+            GCM_TAG_SIZE,
+            GCM_CHUNK_SIZE
+        },
+        error::{
+            Error,
+            Result
+        }
+    },
+    write::{
+        hash_writer::HashWriter
+    }
+};
+
+use std::{
+    io::{
+        Write,
+        Error as IoError,
+        Result as IoResult
+    }
+};
+
+use crypto::{
+    aead::AeadEncryptor,
+    aessafe::AesSafe256Encryptor,
+    blockmodes::CtrMode,
+    symmetriccipher::SynchronousStreamCipher
+};
+
+/// AesWriter struct
+/// 
+/// Encrypts everything written to it using AES-256 in CTR mode.
+/// The IV and key check value are written in front of the encrypted data.
+pub struct AesWriter<W: Write> {
+    /// The underlying writer
+    writer: W,
+    /// The AES-CTR cipher
+    cipher: Box<CtrMode<AesSafe256Encryptor>>,
+    /// Buffer for the encrypted bytes
+    buffer: Vec<u8>
+}
+
+impl<W: Write> AesWriter<W> {
+    /// Creates a new instance with a random IV, wrapping the given writer
+    pub fn new(mut writer: W, key: &Key) -> IoResult<Self> {
+        let mut iv = [0u8; AES_IV_SIZE];
+        encryption::random_bytes(&mut iv);
+        writer.write_all(&iv)?;
+        writer.write_all(&encryption::key_check(key, &iv))?;
+        Ok(
+            Self {
+                writer,
+                cipher: Box::new(encryption::aes_ctr(key, &iv)),
+                buffer: vec![]
+            }
+        )
+    }
+
+    /// Gets a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for AesWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.buffer.resize(buf.len(), 0);
+        self.cipher.process(buf, &mut self.buffer);
+        // The cipher has already advanced, so everything has to be written
+        self.writer.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
+
+/// GcmWriter struct
+/// 
+/// Encrypts everything written to it using AES-256-GCM, in chunks of a fixed size.
+/// The nonce prefix, key check value and chunk size are written in front of the
+/// encrypted data, and every chunk is followed by its authentication tag.
+pub struct GcmWriter<W: Write> {
+    /// The underlying writer
+    writer: W,
+    /// The encryption key
+    key: Key,
+    /// Random, per-file nonce prefix
+    nonce_prefix: [u8; GCM_NONCE_PREFIX_SIZE],
+    /// Plaintext size of each chunk
+    chunk_size: u32,
+    /// Index of the next chunk
+    chunk_index: u32,
+    /// Plaintext of the current chunk
+    buffer: Vec<u8>,
+    /// Buffer for the encrypted chunk
+    out_buffer: Vec<u8>
+}
+
+impl<W: Write> GcmWriter<W> {
+    /// Creates a new instance with a random nonce prefix, wrapping the given writer
+    pub fn new(mut writer: W, key: &Key) -> IoResult<Self> {
+        let mut nonce_prefix = [0u8; GCM_NONCE_PREFIX_SIZE];
+        encryption::random_bytes(&mut nonce_prefix);
+        writer.write_all(&nonce_prefix)?;
+        writer.write_all(&encryption::key_check(key, &nonce_prefix))?;
+        writer.write_all(&GCM_CHUNK_SIZE.to_le_bytes())?;
+        Ok(
+            Self {
+                writer,
+                key: *key,
+                nonce_prefix,
+                chunk_size: GCM_CHUNK_SIZE,
+                chunk_index: 0,
+                buffer: Vec::with_capacity(GCM_CHUNK_SIZE as usize),
+                out_buffer: vec![]
+            }
+        )
+    }
+
+    /// Gets a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Encrypts and writes the first `length` buffered bytes as one chunk
+    fn write_chunk(&mut self, length: usize, is_last: bool) -> IoResult<()> {
+        let mut cipher = encryption::aes_gcm(&self.key, &self.nonce_prefix, self.chunk_index, self.chunk_size, is_last);
+        self.out_buffer.resize(length + GCM_TAG_SIZE, 0);
+        let (ciphertext, tag) = self.out_buffer.split_at_mut(length);
+        cipher.encrypt(&self.buffer[0..length], ciphertext, tag);
+        self.writer.write_all(&self.out_buffer)?;
+        self.buffer.drain(0..length);
+        self.chunk_index = self.chunk_index.checked_add(1)
+            .ok_or_else(|| IoError::other("Too many chunks!"))?;
+        Ok(())
+    }
+
+    /// Writes the last chunk, returning the underlying writer
+    pub fn finish(mut self) -> IoResult<W> {
+        let length = self.buffer.len();
+        self.write_chunk(length, true)?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Write for GcmWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let chunk_size = self.chunk_size as usize;
+        // A full chunk is only written once more data arrives,
+        // as the last chunk has to be marked as such
+        if self.buffer.len() == chunk_size && !buf.is_empty() {
+            self.write_chunk(chunk_size, false)?;
+        }
+        let length = buf.len().min(chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[0..length]);
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the given bytes, sealed using AES-256-GCM
+/// 
+/// Used for encrypting the directory and file headers.
+pub fn write_sealed<W: Write>(writer: W, bytes: &[u8], key: &Key) -> IoResult<()> {
+    let mut gcm_writer = GcmWriter::new(writer, key)?;
+    gcm_writer.write_all(bytes)?;
+    gcm_writer.finish()?;
+    Ok(())
+}
+
+/// CryptWriter enum
+/// 
+/// Wraps a HashWriter in one of the supported encryption algorithms.
+pub enum CryptWriter<W: Write> {
+    /// Raw - no encryption.
+    Raw(HashWriter<W>),
+    /// AES-256 encryption.
+    AES(AesWriter<HashWriter<W>>),
+    /// AES-256-GCM authenticated encryption.
+    AESGCM(GcmWriter<HashWriter<W>>)
+}
+
+impl<W: Write> CryptWriter<W> {
+    /// Creates a new CryptWriter
+    /// 
+    /// Wraps the given HashWriter in a CryptWriter variant corresponding
+    /// to the chosen encryption algorithm.
+    pub fn new(hash_writer: HashWriter<W>, encryption_type: Encryption, key_opt: Option<&Key>) -> Result<Self> {
+        match encryption_type {
+            Encryption::None => Ok(CryptWriter::Raw(hash_writer)),
+            Encryption::AES => {
+                let key = key_opt.ok_or(Error::KeyRequired)?;
+                let aes_writer = AesWriter::new(hash_writer, key)?;
+                Ok(CryptWriter::AES(aes_writer))
+            },
+            Encryption::AESGCM => {
+                let key = key_opt.ok_or(Error::KeyRequired)?;
+                let gcm_writer = GcmWriter::new(hash_writer, key)?;
+                Ok(CryptWriter::AESGCM(gcm_writer))
+            },
+            other => Err(Error::UnsupportedEncryption(other))
+        }
+    }
+
+    /// Gets the encryption type corresponding to this writer variant
+    pub fn get_encryption_type(&self) -> Encryption {
+        match self {
+            CryptWriter::Raw(_) => Encryption::None,
+            CryptWriter::AES(_) => Encryption::AES,
+            CryptWriter::AESGCM(_) => Encryption::AESGCM
+        }
+    }
+
+    /// Finishes encrypting, returning the underlying HashWriter
+    pub fn finish(self) -> IoResult<HashWriter<W>> {
+        match self {
+            CryptWriter::Raw(hash_writer) => Ok(hash_writer),
+            CryptWriter::AES(aes_writer) => Ok(aes_writer.into_inner()),
+            CryptWriter::AESGCM(gcm_writer) => gcm_writer.finish()
+        }
+    }
+}
+
+impl<W: Write> Write for CryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let writer: &mut dyn Write = match self {
+            CryptWriter::Raw(hash_writer) => hash_writer,
+            CryptWriter::AES(aes_writer) => aes_writer,
+            CryptWriter::AESGCM(gcm_writer) => gcm_writer
+        };
+        writer.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        let writer: &mut dyn Write = match self {
+            CryptWriter::Raw(hash_writer) => hash_writer,
+            CryptWriter::AES(aes_writer) => aes_writer,
+            CryptWriter::AESGCM(gcm_writer) => gcm_writer
+        };
+        writer.flush()
+    }
+}
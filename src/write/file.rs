@@ -1,116 +1,101 @@
 use crate::{
     shared::{
-        error::{
-            Error,
-            Result
-        },
-        file_header::FileHeader,
-        file_info::FileInfo,
-        compression::Compression,
-        encryption::Encryption,
-        directory::Directory
+        error::Result,
+        file_info::FileInfo
     },
     write::{
-        comp_writer::CompWriter
+        file_options::FileOptions,
+        staged_file::StagedFile,
+        writer::Writer
     }
 };
 
 use std::{
     io::{
         Write,
-        Seek,
-        SeekFrom,
         Result as IoResult,
     },
-    ops::{
-        Drop
-    }
+    ops::Drop
 };
 
-use blake2::{
-    Blake2s,
-    Digest
+use tracing::{
+    debug,
+    debug_span,
+    Span
 };
 
 /// File
 /// 
 /// Represents a File stream within the archive.
-/// This wraps the internal writer within several layers of
-/// supported encryption/decryption.
-pub struct File<'w, W: Write + Seek + 'w> {
-    /// The compression writer. Corresponds to a supported compression type.
-    comp_writer: CompWriter<'w, W>,
-    /// Byte range of the data.
-    data_begin: u64,
-    /// Hasher for creating the raw checksum
-    raw_hasher: Blake2s,
-    /// Raw data size
-    raw_size: usize,
-    /// Filename,
-    filename: String,
-    /// Directory pointer
-    directory: &'w mut Directory
+/// The data is compressed and encrypted into a spool first, since the entry's
+/// file header, including the data size, precedes the data within the archive.
+/// The spool keeps small files in memory and moves larger ones into a temporary file,
+/// so memory use doesn't grow with the file size.
+/// The data gets copied into the archive once the file is finished.
+pub struct File<'w, W: Write + 'w> {
+    /// The file being written, until it gets committed
+    staged_file: Option<StagedFile>,
+    /// The archive writer the file gets committed to
+    writer: &'w mut Writer<W>,
+    /// Tracing span covering the whole file write
+    span: Span
 }
 
-impl<'w, W: Write + Seek + 'w> File<'w, W> {
+impl<'w, W: Write + 'w> File<'w, W> {
     /// Creates a new File Writer
     /// 
-    /// Wraps a writer and creates a new file with the given parameters
-    pub fn new(writer: &'w mut W, directory: &'w mut Directory, filename: &str, compression_type: Compression, _encryption_type: Encryption) -> Result<Self> {
-        let data_start = writer.seek(SeekFrom::Current(0)).map_err(|_| Error::Unknown)?;
-        println!("BYTE OFFSET OF THIS FILE: {}", data_start);
+    /// Creates a new file with the given parameters within the given archive writer.
+    /// The data is compressed first and encrypted afterwards.
+    /// Unset compression parameters use the codec's defaults.
+    pub fn new(writer: &'w mut Writer<W>, filename: &str, options: FileOptions) -> Result<Self> {
+        let span = debug_span!("write_file", path = filename, compression = ?options.compression);
+        let _entered = span.enter();
+        debug!("Started writing file");
+        let staged_file = writer.stage_file(filename, options)?;
         Ok(
             Self {
-                comp_writer: CompWriter::new(writer, compression_type),
-                data_begin: data_start,
-                filename: String::from(filename),
-                raw_hasher: Blake2s::new(),
-                raw_size: 0,
-                directory: directory
+                staged_file: Some(staged_file),
+                writer,
+                span: span.clone()
             }
         )
     }
 
-    /// Drops the File Writer early, writing the header
-    pub fn finish(self) {}
+    /// Finishes the file, writing it into the archive and adding it to the directory
+    /// 
+    /// Returns the information recorded for this file.
+    /// Dropping the File Writer finishes it as well, but ignores any errors.
+    pub fn finish(mut self) -> Result<FileInfo> {
+        self.commit()
+            .expect("The file is only committed once")
+    }
+
+    /// Commits the file into the archive, unless already done
+    fn commit(&mut self) -> Option<Result<FileInfo>> {
+        let staged_file = self.staged_file.take()?;
+        let _entered = self.span.enter();
+        Some(self.writer.commit(staged_file))
+    }
 }
 
-impl<'w, W: Write + Seek + 'w> Write for File<'w, W> {
+impl<'w, W: Write + 'w> Write for File<'w, W> {
     fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
-        let written  = self.comp_writer.write(buf)?;
-        self.raw_size += written;
-        self.raw_hasher.input(&buf[0..written]);
-        Ok(written)
+        match self.staged_file.as_mut() {
+            Some(staged_file) => staged_file.write(buf),
+            None => Ok(0)
+        }
     }
     
     fn flush(&mut self) -> IoResult<()> {
-        self.comp_writer.flush()
+        match self.staged_file.as_mut() {
+            Some(staged_file) => staged_file.flush(),
+            None => Ok(())
+        }
     }
 }
 
-impl<'w, W: Write + Seek + 'w> Drop for File<'w, W> {
+impl<'w, W: Write + 'w> Drop for File<'w, W> {
     fn drop(&mut self) {
-        let compression_type = self.comp_writer.get_compression_type();
-        let raw_checksum: [u8; 32] = self.raw_hasher.result_reset().into();
-        let data_checksum = self.comp_writer.get_data_hash();
-        let data_end = self.comp_writer.seek(SeekFrom::Current(0)).unwrap();
-        println!("DATA SIZE OF THIS FILE: {}", data_end - self.data_begin);
-        let file_header_begin = data_end;
-        let file_info = FileInfo::default()
-            .with_compression(compression_type)
-            .with_raw_size(self.raw_size as u64)
-            .with_data_size(data_end - self.data_begin)
-            .with_raw_checksum(raw_checksum)
-            .with_data_checksum(data_checksum);
-        let file_header = FileHeader::default()
-            .with_data_range(self.data_begin..data_end)
-            .with_file_info(file_info);
-        bincode::serialize_into(&mut self.comp_writer, &file_header).unwrap();
-        let file_header_end = self.comp_writer.seek(SeekFrom::Current(0)).unwrap();
-        println!("Position after serialize: {}", file_header_end);
-        println!("Size difference: {}", file_header_end - file_header_begin);
-        println!("Written file header from byte #{} to #{}.", file_header_begin, file_header_end);
-        println!("RAW SIZE OF THIS FILE: {}", self.raw_size);
-        self.directory.set_file(&self.filename, file_header_begin..file_header_end);
+        let _ = self.commit();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,103 @@
+use crate::{
+    shared::{
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::Encryption
+    }
+};
+
+/// FileOptions struct
+/// 
+/// Describes how a single file gets written into the archive.
+#[derive(Debug, Clone)]
+pub struct FileOptions {
+    /// The compression algorithm
+    pub compression: Compression,
+    /// The compression parameters
+    pub compression_params: CompressionParams,
+    /// The encryption algorithm. None means the writer's default encryption.
+    pub encryption: Option<Encryption>,
+    /// The number of compression worker threads. None means the writer's default.
+    pub workers: Option<u32>,
+    /// Uncompressed byte size of independently compressed frames. None compresses the data as a whole.
+    pub frame_size: Option<u64>
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self::new(Compression::default())
+    }
+}
+
+impl FileOptions {
+    /// Creates new options using the given compression algorithm and its defaults
+    /// 
+    /// Compressed data gets split into frames of the algorithm's default frame size,
+    /// see `Compression::default_frame_size`.
+    pub fn new(compression_type: Compression) -> Self {
+        Self {
+            frame_size: compression_type.default_frame_size(),
+            compression: compression_type,
+            compression_params: CompressionParams::default(),
+            encryption: None,
+            workers: None
+        }
+    }
+
+    /// ...with a given compression level or preset
+    pub fn with_level(mut self, level: u32) -> Self {
+        self.compression_params = self.compression_params.with_level(level);
+        self
+    }
+
+    /// ...with a given zstd window log
+    /// 
+    /// Larger windows find matches further apart, but need more memory
+    /// for both compression and decompression. Window logs zstd doesn't support,
+    /// e.g. above 31, make writing the file fail.
+    pub fn with_window_log(mut self, window_log: u32) -> Self {
+        self.compression_params = self.compression_params.with_window_log(window_log);
+        self
+    }
+
+    /// ...with zstd long distance matching enabled or disabled
+    pub fn with_long_distance_matching(mut self, long_distance_matching: bool) -> Self {
+        self.compression_params = self.compression_params.with_long_distance_matching(long_distance_matching);
+        self
+    }
+
+    /// ...with a given number of compression worker threads, overriding the writer's default
+    /// 
+    /// Only zstd compresses on multiple threads. A single worker compresses
+    /// on the calling thread.
+    pub fn with_workers(mut self, workers: u32) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// ...with the data compressed in independent frames of the given uncompressed size
+    /// 
+    /// Makes seeking within the file cheap, since only a single frame has to be decompressed,
+    /// at the cost of a slightly worse compression ratio. Uncompressed files ignore this.
+    pub fn with_frame_size(mut self, frame_size: u64) -> Self {
+        self.frame_size = Some(frame_size.max(1));
+        self
+    }
+
+    /// ...with the data compressed as a whole, without frames or a seek table
+    /// 
+    /// Gives the best compression ratio, but seeking within the file becomes O(n):
+    /// seeking backwards decompresses the data again from the start.
+    pub fn without_frames(mut self) -> Self {
+        self.frame_size = None;
+        self
+    }
+
+    /// ...with a given encryption type, overriding the writer's default
+    pub fn with_encryption(mut self, encryption_type: Encryption) -> Self {
+        self.encryption = Some(encryption_type);
+        self
+    }
+}
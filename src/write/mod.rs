@@ -2,7 +2,19 @@
 pub mod writer;
 /// File writer stream
 pub mod file;
+/// Per-file write options
+pub mod file_options;
+/// Staged file writer, committed into the archive later
+pub mod staged_file;
+/// Storage for staged data, in memory or in a temporary file
+pub mod spool;
 /// Compression writer stream
 pub mod comp_writer;
 /// Hash wrapper stream
 pub mod hash_writer;
+/// Position tracking wrapper stream
+pub mod position_writer;
+/// Encryption writer stream
+pub mod crypt_writer;
+/// Rewriting archives into the current format
+pub mod upgrade;
@@ -0,0 +1,49 @@
+use std::{
+    io::{
+        Write,
+        Result as IoResult
+    }
+};
+
+/// PositionWriter struct
+/// 
+/// Wraps a plain writer and keeps track of the number of bytes written,
+/// so archives can be written to sinks that can't seek, like pipes or sockets.
+pub struct PositionWriter<W: Write> {
+    /// The underlying writer
+    writer: W,
+    /// The number of bytes written so far
+    position: u64
+}
+
+impl<W: Write> PositionWriter<W> {
+    /// Creates a new instance, wrapping the given writer
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            position: 0
+        }
+    }
+
+    /// Gets the number of bytes written so far
+    pub fn get_position(&self) -> u64 {
+        self.position
+    }
+
+    /// Unwraps the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for PositionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let written = self.writer.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    fs::File,
+    io::{
+        Read,
+        Write,
+        Seek,
+        SeekFrom,
+        Cursor,
+        Result as IoResult
+    }
+};
+
+/// Number of bytes a spool keeps in memory before moving to a temporary file
+pub const SPOOL_MEMORY_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Spool enum
+///
+/// Holds the stored data of a file until it gets written into the archive. Small files stay in memory,
+/// larger ones move into an anonymous temporary file, so memory use doesn't grow with the file size.
+/// The temporary file is deleted once the spool is dropped.
+pub enum Spool {
+    /// In memory, up to `SPOOL_MEMORY_LIMIT` bytes
+    Memory(Cursor<Vec<u8>>),
+    /// In a temporary file
+    File(File)
+}
+
+impl Spool {
+    /// Creates a new, empty spool in memory
+    pub fn new() -> Self {
+        Spool::Memory(Cursor::new(vec![]))
+    }
+
+    /// Returns a boolean indicating whether or not the data has moved into a temporary file
+    pub fn is_file(&self) -> bool {
+        matches!(self, Spool::File(_))
+    }
+
+    /// Moves the data into a temporary file, keeping the position
+    fn move_to_file(&mut self) -> IoResult<()> {
+        if let Spool::Memory(cursor) = self {
+            let mut file = tempfile::tempfile()?;
+            file.write_all(cursor.get_ref())?;
+            file.seek(SeekFrom::Start(cursor.position()))?;
+            *self = Spool::File(file);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Spool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        if let Spool::Memory(cursor) = self {
+            if cursor.get_ref().len() + buf.len() > SPOOL_MEMORY_LIMIT {
+                self.move_to_file()?;
+            }
+        }
+        match self {
+            Spool::Memory(cursor) => cursor.write(buf),
+            Spool::File(file) => file.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        match self {
+            Spool::Memory(cursor) => cursor.flush(),
+            Spool::File(file) => file.flush()
+        }
+    }
+}
+
+impl Read for Spool {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self {
+            Spool::Memory(cursor) => cursor.read(buf),
+            Spool::File(file) => file.read(buf)
+        }
+    }
+}
+
+impl Seek for Spool {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        match self {
+            Spool::Memory(cursor) => cursor.seek(pos),
+            Spool::File(file) => file.seek(pos)
+        }
+    }
+}
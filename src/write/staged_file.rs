@@ -0,0 +1,250 @@
+use crate::{
+    shared::{
+        error::{
+            Error,
+            Result
+        },
+        file_info::FileInfo,
+        compression::{
+            Compression,
+            CompressionParams
+        },
+        encryption::{
+            Encryption,
+            Key
+        },
+        seek_table::SeekTable
+    },
+    write::{
+        comp_writer::CompWriter,
+        spool::Spool,
+        file_options::FileOptions,
+        crypt_writer::CryptWriter,
+        hash_writer::HashWriter
+    }
+};
+
+use std::{
+    io::{
+        Write,
+        Seek,
+        SeekFrom,
+        Cursor,
+        Result as IoResult
+    }
+};
+
+use blake2::{
+    Blake2s,
+    Digest
+};
+
+/// StagedFile
+///
+/// A file that gets compressed and encrypted into a spool, independently of the archive.
+/// The spool keeps small files in memory and moves larger ones into a temporary file.
+/// Staged files own all of their state, so several of them can be written at once,
+/// e.g. from different threads. Once written, they get committed into the archive
+/// using `Writer::commit`.
+pub struct StagedFile {
+    /// The compression writer, writing into the spool
+    /// 
+    /// Only encrypts if the data is compressed in frames, which happens beforehand.
+    comp_writer: CompWriter<Spool>,
+    /// The frame compressor, if the data is compressed in independent frames
+    frames_opt: Option<FrameCompressor>,
+    /// Hasher for creating the raw checksum
+    raw_hasher: Blake2s,
+    /// Raw data size
+    raw_size: u64,
+    /// Filename
+    filename: String,
+    /// The compression algorithm
+    compression: Compression,
+    /// The compression parameters, with all defaults filled in
+    compression_params: CompressionParams
+}
+
+impl StagedFile {
+    /// Creates a new staged file
+    ///
+    /// The data is compressed first and encrypted afterwards,
+    /// exactly as it would be when writing it into the archive directly.
+    pub fn new(filename: &str, options: FileOptions, key_opt: Option<&Key>) -> Result<Self> {
+        let hash_writer = HashWriter::new(Spool::new());
+        let crypt_writer = CryptWriter::new(hash_writer, options.encryption.unwrap_or_default(), key_opt)
+            .map_err(|error| error.map_io(|source| Error::CantWriteFile {
+                path: String::from(filename),
+                source
+            }))?;
+        let compression_params = options.compression_params.resolve(&options.compression);
+        let workers = options.workers.unwrap_or(1);
+        let write_error = |error: Error| error.map_io(|source| Error::CantWriteFile {
+            path: String::from(filename),
+            source
+        });
+        let (comp_writer, frames_opt) = match options.frame_size {
+            Some(frame_size) if options.compression != Compression::None => {
+                let frames = FrameCompressor::new(frame_size, options.compression.clone(), compression_params.clone(), workers)
+                    .map_err(write_error)?;
+                (CompWriter::new(crypt_writer, Compression::None, &compression_params, 1).map_err(write_error)?, Some(frames))
+            },
+            _ => (CompWriter::new(crypt_writer, options.compression.clone(), &compression_params, workers).map_err(write_error)?, None)
+        };
+        Ok(
+            Self {
+                comp_writer,
+                frames_opt,
+                raw_hasher: Blake2s::new(),
+                raw_size: 0,
+                filename: String::from(filename),
+                compression: options.compression,
+                compression_params
+            }
+        )
+    }
+
+    /// Gets the filename this file gets committed under
+    pub fn get_filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Finishes compression and encryption
+    ///
+    /// Returns the filename, the spool holding the stored data, rewound to its start, and the file information.
+    pub(crate) fn finish(mut self) -> Result<(String, Spool, FileInfo)> {
+        let filename = &self.filename;
+        let write_error = |source| Error::CantWriteFile {
+            path: filename.clone(),
+            source
+        };
+        let seek_table_opt = match self.frames_opt.take() {
+            Some(frames) => Some(frames.finish(&mut self.comp_writer).map_err(write_error)?),
+            None => None
+        };
+        let encryption_type = self.comp_writer.get_encryption_type();
+        let raw_checksum: [u8; 32] = self.raw_hasher.result_reset().into();
+        let data_checksum = self.comp_writer.get_data_hash()
+            .map_err(write_error)?;
+        let mut data = self.comp_writer.into_inner();
+        let data_size = data.seek(SeekFrom::End(0))
+            .and_then(|data_size| data.rewind().map(|_| data_size))
+            .map_err(write_error)?;
+        let mut file_info = FileInfo::default()
+            .with_compression(self.compression)
+            .with_compression_params(self.compression_params)
+            .with_encryption(encryption_type)
+            .with_raw_size(self.raw_size)
+            .with_data_size(data_size)
+            .with_raw_checksum(raw_checksum)
+            .with_data_checksum(data_checksum);
+        if let Some(seek_table) = seek_table_opt {
+            file_info = file_info.with_seek_table(seek_table);
+        }
+        Ok((self.filename, data, file_info))
+    }
+}
+
+impl Write for StagedFile {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let written = match self.frames_opt.as_mut() {
+            Some(frames) => frames.write(buf, &mut self.comp_writer)?,
+            None => self.comp_writer.write(buf)?
+        };
+        self.raw_size += written as u64;
+        self.raw_hasher.input(&buf[0..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.comp_writer.flush()
+    }
+}
+
+/// FrameCompressor
+/// 
+/// Compresses data in independent frames of a fixed uncompressed size, recording a seek table.
+struct FrameCompressor {
+    /// The compression algorithm
+    compression: Compression,
+    /// The compression parameters, with all defaults filled in
+    compression_params: CompressionParams,
+    /// The number of compression worker threads
+    workers: u32,
+    /// The frame currently being compressed, into its own buffer
+    frame_opt: Option<CompWriter<Cursor<Vec<u8>>>>,
+    /// Raw data size of the current frame
+    frame_raw_size: u64,
+    /// Compressed size of all finished frames
+    compressed_size: u64,
+    /// The seek table of all finished frames
+    seek_table: SeekTable
+}
+
+impl FrameCompressor {
+    /// Creates a new frame compressor
+    /// 
+    /// The first frame is created right away, so invalid compression parameters are caught early.
+    fn new(frame_size: u64, compression: Compression, compression_params: CompressionParams, workers: u32) -> Result<Self> {
+        let mut frames = Self {
+            compression,
+            compression_params,
+            workers,
+            frame_opt: None,
+            frame_raw_size: 0,
+            compressed_size: 0,
+            seek_table: SeekTable::new(frame_size)
+        };
+        frames.frame_opt = Some(frames.new_frame()?);
+        Ok(frames)
+    }
+
+    /// Creates the compression writer of a new frame, compressing into its own buffer
+    fn new_frame(&self) -> Result<CompWriter<Cursor<Vec<u8>>>> {
+        let crypt_writer = CryptWriter::new(HashWriter::new(Cursor::new(vec![])), Encryption::None, None)?;
+        CompWriter::new(crypt_writer, self.compression.clone(), &self.compression_params, self.workers)
+    }
+
+    /// Compresses data into the current frame, up to the end of the frame
+    /// 
+    /// Finished frames are written into the given writer.
+    fn write<W: Write>(&mut self, buf: &[u8], writer: &mut W) -> IoResult<usize> {
+        let remaining = self.seek_table.frame_size - self.frame_raw_size;
+        let length = (buf.len() as u64).min(remaining) as usize;
+        let frame = match self.frame_opt.as_mut() {
+            Some(frame) => frame,
+            None => {
+                let frame = self.new_frame()
+                    .map_err(Error::into_io)?;
+                self.frame_opt.insert(frame)
+            }
+        };
+        let written = frame.write(&buf[0..length])?;
+        self.frame_raw_size += written as u64;
+        if self.frame_raw_size == self.seek_table.frame_size {
+            self.finish_frame(writer)?;
+        }
+        Ok(written)
+    }
+
+    /// Finishes the current frame, if it holds any data, and writes it into the given writer
+    fn finish_frame<W: Write>(&mut self, writer: &mut W) -> IoResult<()> {
+        let frame_opt = self.frame_opt.take()
+            .filter(|_| self.frame_raw_size > 0);
+        if let Some(mut frame) = frame_opt {
+            frame.get_data_hash()?;
+            let bytes = frame.into_inner().into_inner();
+            writer.write_all(&bytes)?;
+            self.compressed_size += bytes.len() as u64;
+            self.seek_table.push_frame(self.compressed_size);
+        }
+        self.frame_raw_size = 0;
+        Ok(())
+    }
+
+    /// Finishes the last frame and returns the seek table
+    fn finish<W: Write>(mut self, writer: &mut W) -> IoResult<SeekTable> {
+        self.finish_frame(writer)?;
+        Ok(self.seek_table)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    shared::{
+        archive_header::ArchiveHeader,
+        error::Result
+    },
+    read::archive::Archive,
+    write::writer::Writer
+};
+
+use std::{
+    io::{
+        Read,
+        Write,
+        Seek
+    },
+    ops::Range
+};
+
+use tracing::{
+    debug,
+    debug_span
+};
+
+/// Rewrites an archive of any supported format revision into the current format
+/// 
+/// Entry data is copied as-is, without recompressing or re-encrypting it.
+/// Only the archive header, the file headers and the directory are written anew.
+/// The key derivation parameters and the index encryption are kept, using the archive's key.
+/// Returns the byte range of the new directory.
+pub fn upgrade<R: Read + Seek, W: Write>(archive: &mut Archive<R>, sink: W) -> Result<Range<u64>> {
+    let _span = debug_span!("upgrade_archive", format_version = %archive.get_format_version()).entered();
+    let header = ArchiveHeader {
+        key_derivation: archive.get_key_derivation().cloned(),
+        ..ArchiveHeader::default()
+    };
+    let mut writer = Writer::with_header(sink, header, archive.get_key().cloned())?;
+    if archive.is_index_encrypted() {
+        writer.set_index_encryption(true)?;
+    }
+    // Keep the original order of the entries
+    let mut paths = archive.get_file_list();
+    paths.sort_by_key(|path| archive.get_file_offset(path));
+    for path in paths.iter() {
+        let (file_info, mut data) = archive.get_stored_data(path)?;
+        writer.append_entry(path, &mut data, file_info)?;
+    }
+    debug!(entries = paths.len(), "Upgraded archive");
+    writer.finish()
+}
@@ -1,70 +1,355 @@
 use std::{
     io::{
-        Seek,
+        Read,
         Write,
-        SeekFrom
-    }
+        Error as IoError,
+        ErrorKind,
+        Result as IoResult
+    },
+    ops::Range
+};
+
+use tracing::{
+    debug,
+    debug_span
 };
 
 use crate::{
     shared::{
         compression::Compression,
-        encryption::Encryption,
+        encryption::{
+            Encryption,
+            Key
+        },
         directory::Directory,
         archive_header::ArchiveHeader,
+        format_version::FormatVersion,
+        trailer::Trailer,
+        frame::{
+            ENTRY_MARKER,
+            DIRECTORY_MARKER,
+            ENTRY_FRAME_SIZE
+        },
+        file_header::FileHeader,
+        file_info::FileInfo,
+        key_derivation::KeyDerivation,
+        encoding::Encode,
         error::{
             Error,
-            Result
+            Result,
+            Source
         }
     },
     write::{
-        file::File
+        file::File,
+        file_options::FileOptions,
+        staged_file::StagedFile,
+        position_writer::PositionWriter,
+        crypt_writer
     }
 };
+
 /// The Writer struct
 /// 
 /// Used for creating .var Archives
-pub struct Writer<W: Write + Seek> {
+/// The archive is written front to back, so any sink works, including ones that can't seek.
+pub struct Writer<W: Write> {
     /// The internal, root-level writer
-    sink: W,
+    sink: PositionWriter<W>,
+    /// The archive header, written in front of the first entry
+    header: ArchiveHeader,
+    /// Whether the magic bytes, the format version and the archive header have been written
+    header_written: bool,
     /// The directory struct, mapping filenames to byte ranges
-    directory: Directory
+    directory: Directory,
+    /// The encryption used for new files
+    encryption: Encryption,
+    /// Optional encryption key
+    key_opt: Option<Key>,
+    /// The number of compression worker threads used for new files
+    workers: u32,
+    /// Whether the archive has been finished, successfully or not
+    finished: bool
 }
 
 /// Creates a new Writer, wrapping a given Write struct
-impl<'w, W: Write + Seek> Writer<W> {
+impl<'w, W: Write> Writer<W> {
     /// Creates a new instance, wrapping the given writer
-    pub fn new(mut sink: W) -> Result<Writer<W>> {
-        sink.write(b"VAR")
-            .map_err(|_| Error::CantWriteMagicBytes)?;
-        let archive_header = ArchiveHeader::default();
-        bincode::serialize_into(&mut sink, &archive_header)
-            .map_err(|_| Error::CantWriteHeader)?;
+    pub fn new(sink: W) -> Result<Writer<W>> {
+        Self::with_header(sink, ArchiveHeader::default(), None)
+    }
+
+    /// Creates a new instance with an optional encryption key, using the given archive header
+    /// 
+    /// Nothing gets written until the first file, so the header can still be changed.
+    pub(crate) fn with_header(sink: W, header: ArchiveHeader, key_opt: Option<Key>) -> Result<Writer<W>> {
         Ok(
             Self {
-                sink: sink,
-                directory: Directory::default()
+                sink: PositionWriter::new(sink),
+                header,
+                header_written: false,
+                directory: Directory::default(),
+                encryption: Encryption::default(),
+                key_opt,
+                workers: num_cpus::get() as u32,
+                finished: false
             }
         )
     }
+
+    /// Creates a new instance with a given encryption key, wrapping the given writer
+    /// 
+    /// Files are AES encrypted by default.
+    pub fn new_with_key(sink: W, key: Key) -> Result<Writer<W>> {
+        let mut writer = Self::with_header(sink, ArchiveHeader::default(), Some(key))?;
+        writer.encryption = Encryption::AES;
+        Ok(writer)
+    }
+
+    /// Creates a new password protected instance, wrapping the given writer
+    /// 
+    /// The key is derived from the password using the default scrypt parameters.
+    /// Files are AES encrypted by default.
+    pub fn new_with_password(sink: W, password: &str) -> Result<Writer<W>> {
+        Self::new_with_key_derivation(sink, password, KeyDerivation::default())
+    }
+
+    /// Creates a new password protected instance with the given key derivation parameters
+    /// 
+    /// The parameters are stored in the archive header. Files are AES encrypted by default.
+    pub fn new_with_key_derivation(sink: W, password: &str, key_derivation: KeyDerivation) -> Result<Writer<W>> {
+        let key = key_derivation.derive_key(password)?;
+        let header = ArchiveHeader {
+            key_derivation: Some(key_derivation),
+            ..ArchiveHeader::default()
+        };
+        let mut writer = Self::with_header(sink, header, Some(key))?;
+        writer.encryption = Encryption::AES;
+        Ok(writer)
+    }
+
+    /// Sets the encryption used for files written from now on
+    /// 
+    /// Any encryption other than `Encryption::None` requires a key.
+    /// Fails with `Error::UnsupportedEncryption` for reserved, unimplemented ones.
+    pub fn set_encryption(&mut self, encryption_type: Encryption) -> Result<()> {
+        if !encryption_type.is_supported() {
+            return Err(Error::UnsupportedEncryption(encryption_type));
+        }
+        if encryption_type != Encryption::None && self.key_opt.is_none() {
+            return Err(Error::KeyRequired);
+        }
+        self.encryption = encryption_type;
+        Ok(())
+    }
     
+    /// Sets the number of compression worker threads used for files written from now on
+    /// 
+    /// Defaults to the number of CPUs. Only zstd compresses on multiple threads.
+    /// A single worker compresses on the calling thread.
+    pub fn set_workers(&mut self, workers: u32) {
+        self.workers = workers.max(1);
+    }
+
+    /// Enables or disables encryption of the directory and all file headers
+    /// 
+    /// This hides the file names and all file information from anyone without the key.
+    /// Requires a key and has to be set before any file is written.
+    pub fn set_index_encryption(&mut self, encrypt_index: bool) -> Result<()> {
+        if self.key_opt.is_none() {
+            return Err(Error::KeyRequired);
+        }
+        if self.header_written {
+            return Err(Error::FilesAlreadyWritten);
+        }
+        self.header.index_encryption = if encrypt_index {
+            Encryption::AESGCM
+        } else {
+            Encryption::None
+        };
+        Ok(())
+    }
+
+    /// Writes the magic bytes, the format version and the archive header, unless already written
+    fn write_header(&mut self) -> Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        self.sink.write_all(b"VAR")
+            .map_err(Error::CantWriteMagicBytes)?;
+        self.sink.write_all(&FormatVersion::CURRENT.to_bytes())
+            .map_err(|error| Error::CantWriteHeader(error.into()))?;
+        self.header.encode(&mut self.sink)
+            .map_err(|error| Error::CantWriteHeader(error.into()))?;
+        Ok(())
+    }
+
+    /// Gets the key used for encrypting the directory and file headers, if any
+    fn get_index_key(&self) -> Option<&Key> {
+        match self.header.index_encryption {
+            Encryption::None => None,
+            _ => self.key_opt.as_ref()
+        }
+    }
+
     /// Initiates a new file writer at the given path
     pub fn write_file(&'w mut self, path: &str, compression_type: Compression) -> Result<File<'w, W>> {
-        File::new(&mut self.sink, &mut self.directory,path, compression_type, Encryption::default())
+        self.write_file_with_options(path, FileOptions::new(compression_type))
+    }
+
+    /// Initiates a new file writer at the given path, using the given compression level
+    /// 
+    /// The level is passed on to the codec: the zstd level, the LZ4 level, the LZMA preset,
+    /// or the deflate/BZIP2 level.
+    /// LZMA presets range from 0 to 9, higher ones fail.
+    pub fn write_file_with_level(&'w mut self, path: &str, compression_type: Compression, level: u32) -> Result<File<'w, W>> {
+        self.write_file_with_options(path, FileOptions::new(compression_type).with_level(level))
+    }
+
+    /// Initiates a new file writer at the given path, using the given options
+    /// 
+    /// The compression parameters used get recorded in the file's FileInfo.
+    pub fn write_file_with_options(&'w mut self, path: &str, options: FileOptions) -> Result<File<'w, W>> {
+        self.write_header()?;
+        File::new(self, path, options)
+    }
+
+    /// Stages a new file at the given path, using the given options
+    /// 
+    /// The staged file is compressed and encrypted into a spool and doesn't borrow the writer,
+    /// so any number of files can be staged at once, e.g. on different threads.
+    /// Staged files get written into the archive using `commit`.
+    pub fn stage_file(&self, path: &str, options: FileOptions) -> Result<StagedFile> {
+        debug!(path, compression = ?options.compression, "Staging file");
+        StagedFile::new(path, self.with_defaults(options), self.key_opt.as_ref())
+    }
+
+    /// Commits a staged file into the archive
+    /// 
+    /// The resulting entry is the same as if the file had been written using `write_file_with_options`.
+    /// Files are stored in the order they get committed.
+    /// Returns the information recorded for the file.
+    pub fn commit(&mut self, staged_file: StagedFile) -> Result<FileInfo> {
+        let _span = debug_span!("commit_file", path = staged_file.get_filename()).entered();
+        let (filename, mut data, file_info) = staged_file.finish()?;
+        self.append_entry(&filename, &mut data, file_info)
+    }
+
+    /// Appends already compressed/encrypted data as a new entry, preceded by its frame and file header
+    /// 
+    /// The data is copied as-is, the file info has to describe it.
+    /// Exactly `file_info.data_size` bytes get copied.
+    pub(crate) fn append_entry<D: Read>(&mut self, filename: &str, data: &mut D, file_info: FileInfo) -> Result<FileInfo> {
+        self.write_header()?;
+        let file_info = file_info.with_filename(filename);
+        let header_error = |source| Error::CantWriteFileHeader {
+            path: String::from(filename),
+            source
+        };
+        let header_key_opt = self.get_index_key().cloned();
+        // The header's size doesn't depend on the data range, which is only known once the size is
+        let placeholder = FileHeader::default()
+            .with_data_range(0..0)
+            .with_file_info(file_info.clone());
+        let file_header_size = encode_file_header(&placeholder, header_key_opt.as_ref())
+            .map_err(header_error)?
+            .len() as u64;
+        let header_begin = self.sink.get_position() + ENTRY_FRAME_SIZE;
+        let data_begin = header_begin + file_header_size;
+        let data_end = data_begin + file_info.data_size;
+        let file_header = FileHeader::default()
+            .with_data_range(data_begin..data_end)
+            .with_file_info(file_info.clone());
+        let file_header_bytes = encode_file_header(&file_header, header_key_opt.as_ref())
+            .map_err(header_error)?;
+        self.sink.write_all(&ENTRY_MARKER)
+            .and_then(|_| file_header_size.encode(&mut self.sink))
+            .and_then(|_| self.sink.write_all(&file_header_bytes))
+            .map_err(header_error)?;
+        let write_error = |source| Error::CantWriteFile {
+            path: String::from(filename),
+            source
+        };
+        let data_size = std::io::copy(&mut data.take(file_info.data_size), &mut self.sink)
+            .map_err(write_error)?;
+        if data_size != file_info.data_size {
+            return Err(write_error(IoError::from(ErrorKind::UnexpectedEof)));
+        }
+        debug!(
+            raw_size = file_info.raw_size,
+            data_size = file_info.data_size,
+            data_begin,
+            "Appended entry"
+        );
+        self.directory.set_file(filename, header_begin..data_begin);
+        Ok(file_info)
+    }
+
+    /// Fills in the writer's defaults for any unset file option
+    fn with_defaults(&self, mut options: FileOptions) -> FileOptions {
+        options.encryption = options.encryption.or_else(|| Some(self.encryption.clone()));
+        options.workers = options.workers.or(Some(self.workers));
+        options
     }
     
-    /// Finishes and drops the archive writer
-    pub fn finish(self) {}
+    /// Finishes the archive, writing the directory and the trailer pointing to it
+    /// 
+    /// Returns the byte range of the directory.
+    /// Dropping the writer finishes the archive as well, but ignores any errors.
+    pub fn finish(mut self) -> Result<Range<u64>> {
+        self.write_directory()
+    }
+
+    /// Writes the directory marker and the directory, followed by the trailer pointing to it
+    fn write_directory(&mut self) -> Result<Range<u64>> {
+        self.finished = true;
+        let _span = debug_span!("write_directory", entries = self.directory.file_headers.len()).entered();
+        self.write_header()?;
+        self.sink.write_all(&DIRECTORY_MARKER)
+            .map_err(|error| Error::CantWriteDirectory {
+                offset: self.sink.get_position(),
+                source: error.into()
+            })?;
+        let directory_begin = self.sink.get_position();
+        let write_error = |source: Source| Error::CantWriteDirectory {
+            offset: directory_begin,
+            source
+        };
+        match self.get_index_key().cloned() {
+            Some(key) => {
+                crypt_writer::write_sealed(&mut self.sink, &self.directory.to_bytes(), &key)
+                    .map_err(|error| write_error(error.into()))?;
+            },
+            None => self.directory.encode(&mut self.sink)
+                .map_err(|error| write_error(error.into()))?
+        }
+        let directory_end = self.sink.get_position();
+        Trailer::new(directory_begin..directory_end).encode(&mut self.sink)
+            .map_err(Error::CantWriteTrailer)?;
+        self.sink.flush()
+            .map_err(Error::CantWriteTrailer)?;
+        debug!(directory_begin, directory_end, "Finished writing archive");
+        Ok(directory_begin..directory_end)
+    }
 }
 
-impl<'w, W: Write + Seek> Drop for Writer<W> {
+/// Encodes a file header, sealing it if a header key is given
+fn encode_file_header(file_header: &FileHeader, header_key_opt: Option<&Key>) -> IoResult<Vec<u8>> {
+    match header_key_opt {
+        Some(key) => {
+            let mut bytes = vec![];
+            crypt_writer::write_sealed(&mut bytes, &file_header.to_bytes(), key)?;
+            Ok(bytes)
+        },
+        None => Ok(file_header.to_bytes())
+    }
+}
+
+impl<W: Write> Drop for Writer<W> {
     fn drop(&mut self) {
-        let directory_begin = self.sink.seek(SeekFrom::End(0)).unwrap();
-        bincode::serialize_into(&mut self.sink, &self.directory).unwrap();
-        let directory_end = self.sink.seek(SeekFrom::Current(0)).unwrap();
-        self.sink.seek(SeekFrom::Start(3)).unwrap();
-        let mut archive_header = ArchiveHeader::default();
-        archive_header.directory_range = directory_begin..directory_end;
-        bincode::serialize_into(&mut self.sink, &archive_header).unwrap();
-    }
-}
\ No newline at end of file
+        if !self.finished {
+            let _ = self.write_directory();
+        }
+    }
+}
@@ -0,0 +1,152 @@
+extern crate vapor_archive as var;
+
+use var::{
+    Compression,
+    shared::{
+        archive_header::ArchiveHeader,
+        directory::Directory,
+        file_header::FileHeader,
+        file_info::FileInfo,
+        file_metadata::FileMetadata,
+        seek_table::SeekTable,
+        compression::CompressionParams,
+        encryption::Encryption,
+        key_derivation::KeyDerivation,
+        trailer::Trailer,
+        encoding::{
+            Encode,
+            Decode
+        }
+    }
+};
+
+use std::{
+    io::ErrorKind,
+    error::Error
+};
+
+use chrono::{
+    TimeZone,
+    Utc
+};
+
+/// The archive header stored in `tests/vectors/archive_header.bin`
+fn golden_archive_header() -> ArchiveHeader {
+    ArchiveHeader {
+        version: *b"0.1.0           ",
+        key_derivation: Some(KeyDerivation::Scrypt {
+            salt: [0xA5; 16],
+            log_n: 15,
+            r: 8,
+            p: 1
+        }),
+        index_encryption: Encryption::AESGCM
+    }
+}
+
+/// The directory stored in `tests/vectors/directory.bin`
+fn golden_directory() -> Directory {
+    let mut directory = Directory::default();
+    directory.set_file("docs/bsd.md", 1000..1200);
+    directory.set_file("a.txt", 20..220);
+    directory
+}
+
+/// The file header stored in `tests/vectors/file_header.bin`
+fn golden_file_header() -> FileHeader {
+    let metadata = FileMetadata {
+        readonly: true,
+        permissions_opt: Some(0o644),
+        added_at: Utc.timestamp_millis_opt(1_590_000_000_123).unwrap(),
+        modified_at: Some(Utc.timestamp_millis_opt(1_580_000_000_000).unwrap()),
+        created_at: None,
+        accessed_at: None
+    };
+    let file_info = FileInfo::default()
+        .with_compression(Compression::ZStandard)
+        .with_compression_params(CompressionParams::default().with_level(19).with_window_log(27))
+        .with_encryption(Encryption::None)
+        .with_raw_size(1544)
+        .with_data_size(802)
+        .with_raw_checksum([0x11; 32])
+        .with_data_checksum([0x22; 32])
+        .with_seek_table(SeekTable {
+            frame_size: 1024,
+            frame_ends: vec![402, 802]
+        })
+        .with_filename("bsd.md");
+    FileHeader::default()
+        .with_data_range(7..809)
+        .with_file_info(FileInfo { metadata, ..file_info })
+}
+
+#[test]
+fn test_encoding_archive_header() -> Result<(), Box<dyn Error>> {
+    let golden = std::fs::read("tests/vectors/archive_header.bin")?;
+    assert_eq!(golden_archive_header().to_bytes(), golden);
+    let header = ArchiveHeader::decode(&mut &golden[..])?;
+    assert_eq!(header.key_derivation, golden_archive_header().key_derivation);
+    assert_eq!(header.index_encryption, Encryption::AESGCM);
+    Ok(())
+}
+
+#[test]
+fn test_encoding_trailer() -> Result<(), Box<dyn Error>> {
+    let golden = std::fs::read("tests/vectors/trailer.bin")?;
+    assert_eq!(Trailer::new(4096..4160).to_bytes(), golden);
+    assert_eq!(Trailer::decode(&mut &golden[..])?, Trailer::new(4096..4160));
+    Ok(())
+}
+
+#[test]
+fn test_encoding_directory() -> Result<(), Box<dyn Error>> {
+    let golden = std::fs::read("tests/vectors/directory.bin")?;
+    // Entries are sorted by path, so the encoding doesn't depend on the insertion order
+    assert_eq!(golden_directory().to_bytes(), golden);
+    let directory = Directory::decode(&mut &golden[..])?;
+    assert_eq!(directory.get_file("a.txt"), Some(20..220));
+    assert_eq!(directory.get_file("docs/bsd.md"), Some(1000..1200));
+    assert_eq!(directory.get_file_list().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_encoding_file_header() -> Result<(), Box<dyn Error>> {
+    let golden = std::fs::read("tests/vectors/file_header.bin")?;
+    assert_eq!(golden_file_header().to_bytes(), golden);
+    let file_header = FileHeader::decode(&mut &golden[..])?;
+    assert_eq!(file_header.data_range, 7..809);
+    let file_info = file_header.file_info;
+    assert_eq!(file_info.compression, Compression::ZStandard);
+    assert_eq!(file_info.compression_params.level, Some(19));
+    assert_eq!(file_info.compression_params.window_log, Some(27));
+    assert_eq!(file_info.filename, "bsd.md");
+    assert_eq!(file_info.metadata.permissions_opt, Some(0o644));
+    assert_eq!(file_info.metadata.added_at.timestamp_millis(), 1_590_000_000_123);
+    assert_eq!(file_info.raw_checksum, [0x11; 32]);
+    let seek_table = file_info.seek_table.expect("Expected a seek table!");
+    assert_eq!(seek_table.get_frame_range(1), Some(402..802));
+    Ok(())
+}
+
+#[test]
+fn test_encoding_invalid_data() {
+    // Option tags, booleans and key derivation tags only have a fixed set of valid values
+    let mut bytes = golden_archive_header().to_bytes();
+    bytes[16] = 2;
+    match ArchiveHeader::decode(&mut &bytes[..]) {
+        Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData),
+        Ok(_) => panic!("Expected invalid data!")
+    }
+    let mut bytes = Trailer::new(4096..4160).to_bytes();
+    bytes[16] = b'X';
+    match Trailer::decode(&mut &bytes[..]) {
+        Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData),
+        Ok(_) => panic!("Expected invalid data!")
+    }
+    let bytes = golden_directory().to_bytes();
+    match Directory::decode(&mut &bytes[..bytes.len() - 1]) {
+        Err(error) => assert_eq!(error.kind(), ErrorKind::UnexpectedEof),
+        Ok(_) => panic!("Expected an unexpected EOF!")
+    }
+}
@@ -0,0 +1,424 @@
+extern crate vapor_archive as var;
+
+use var::{
+    VarWriter,
+    VarArchive,
+    VarStreamReader,
+    VarFileOptions,
+    Compression,
+    read::crypt_reader::GcmReader,
+    write::{
+        crypt_writer::GcmWriter,
+        upgrade::upgrade
+    },
+    shared::{
+        encryption::{
+            Encryption,
+            Key,
+            GCM_CHUNK_SIZE
+        },
+        key_derivation::KeyDerivation,
+        error::Error as VarError
+    }
+};
+
+use std::{
+    fs::{
+        File
+    },
+    io::{
+        Read,
+        Write,
+        Seek,
+        SeekFrom,
+        Cursor,
+        ErrorKind
+    },
+    error::Error
+};
+
+const KEY: Key = *b"0123456789abcdef0123456789abcdef";
+
+/// Writes the given test file into an in-memory, encrypted archive
+fn write_encrypted_archive(name: &str, compression: Compression) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new_with_key(&mut archive_bytes, KEY)?;
+        let mut var_file = writer.write_file(name, compression)?;
+        let mut input_file = File::open(format!("tests/files/{}", name))?;
+        std::io::copy(&mut input_file, &mut var_file)?;
+    }
+    Ok(archive_bytes.into_inner())
+}
+
+#[test]
+fn test_encryption_aes_compressed() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_encrypted_archive("sales_records.csv", Compression::default())?;
+    let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), KEY)?;
+    assert_eq!(archive.get_file_info("sales_records.csv")?.encryption, Encryption::AES);
+    let mut file_content = vec![];
+    archive.get_file("sales_records.csv")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/sales_records.csv")?);
+    assert!(archive.verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_aes_uncompressed() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_encrypted_archive("bsd.md", Compression::None)?;
+    let content = std::fs::read("tests/files/bsd.md")?;
+    // The plaintext must not show up in the archive
+    assert!(archive_bytes.windows(64).all(|window| window != &content[0..64]));
+    let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), KEY)?;
+    let mut file_content = vec![];
+    archive.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, content);
+    Ok(())
+}
+
+#[test]
+fn test_encryption_wrong_key() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_encrypted_archive("bsd.md", Compression::default())?;
+    let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), [0u8; 32])?;
+    match archive.get_file("bsd.md") {
+        Err(VarError::WrongKey) => {},
+        _ => panic!("Expected a wrong key error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_missing_key() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_encrypted_archive("bsd.md", Compression::default())?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    match archive.get_file("bsd.md") {
+        Err(VarError::KeyRequired) => {},
+        _ => panic!("Expected a key required error!")
+    }
+    Ok(())
+}
+
+/// Writes the given test file into an in-memory, password protected archive
+fn write_password_archive(name: &str, key_derivation: KeyDerivation) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new_with_key_derivation(&mut archive_bytes, "hunter2", key_derivation)?;
+        let mut var_file = writer.write_file(name, Compression::default())?;
+        let mut input_file = File::open(format!("tests/files/{}", name))?;
+        std::io::copy(&mut input_file, &mut var_file)?;
+    }
+    Ok(archive_bytes.into_inner())
+}
+
+#[test]
+fn test_encryption_password_scrypt() -> Result<(), Box<dyn Error>> {
+    let key_derivation = KeyDerivation::scrypt(4, 8, 1);
+    let archive_bytes = write_password_archive("bsd.md", key_derivation.clone())?;
+    let mut archive = VarArchive::new_with_password(Cursor::new(archive_bytes), "hunter2")?;
+    assert_eq!(archive.get_key_derivation(), Some(&key_derivation));
+    let mut file_content = vec![];
+    archive.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    Ok(())
+}
+
+#[test]
+fn test_encryption_password_pbkdf2() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_password_archive("bsd.md", KeyDerivation::pbkdf2(1000))?;
+    let mut archive = VarArchive::new_with_password(Cursor::new(archive_bytes), "hunter2")?;
+    let mut file_content = vec![];
+    archive.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    Ok(())
+}
+
+#[test]
+fn test_encryption_wrong_password() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_password_archive("bsd.md", KeyDerivation::scrypt(4, 8, 1))?;
+    let mut archive = VarArchive::new_with_password(Cursor::new(archive_bytes), "hunter3")?;
+    match archive.get_file("bsd.md") {
+        Err(VarError::WrongKey) => {},
+        _ => panic!("Expected a wrong key error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_invalid_scrypt() -> Result<(), Box<dyn Error>> {
+    // scrypt requires log_n < 16 * r
+    assert!(matches!(KeyDerivation::scrypt(16, 1, 1).derive_key("hunter2"), Err(VarError::InvalidKeyDerivation)));
+    // 128 * r * 2^log_n bytes of memory, 2TiB here
+    assert!(matches!(KeyDerivation::scrypt(20, 1 << 14, 1).derive_key("hunter2"), Err(VarError::InvalidKeyDerivation)));
+    // 128 * r * p bytes of memory for the blocks, 64GiB here
+    assert!(matches!(KeyDerivation::scrypt(1, 1, 1 << 29).derive_key("hunter2"), Err(VarError::InvalidKeyDerivation)));
+    assert!(KeyDerivation::scrypt(4, 1, 1).derive_key("hunter2").is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_invalid_pbkdf2() -> Result<(), Box<dyn Error>> {
+    assert!(matches!(KeyDerivation::pbkdf2(0).derive_key("hunter2"), Err(VarError::InvalidKeyDerivation)));
+    // Would take hours
+    assert!(matches!(KeyDerivation::pbkdf2(u32::MAX).derive_key("hunter2"), Err(VarError::InvalidKeyDerivation)));
+    assert!(KeyDerivation::pbkdf2(1).derive_key("hunter2").is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_crafted_scrypt() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_password_archive("bsd.md", KeyDerivation::scrypt(4, 8, 1))?;
+    let archive = VarArchive::new_with_password(Cursor::new(archive_bytes.clone()), "hunter2")?;
+    let salt = match archive.get_key_derivation() {
+        Some(KeyDerivation::Scrypt { salt, .. }) => *salt,
+        _ => panic!("Expected scrypt parameters!")
+    };
+    // log_n, r and p follow the salt
+    let log_n_offset = archive_bytes.windows(salt.len()).position(|window| window == salt).unwrap() + salt.len();
+    // N too large for r, then a p passing the r * p limit, but whose blocks would need 64GiB of memory
+    for (log_n, r, p) in [(16u8, 1u32, 1u32), (1, 1, 1 << 29)] {
+        let mut archive_bytes = archive_bytes.clone();
+        archive_bytes[log_n_offset] = log_n;
+        archive_bytes[log_n_offset + 1..log_n_offset + 5].copy_from_slice(&r.to_le_bytes());
+        archive_bytes[log_n_offset + 5..log_n_offset + 9].copy_from_slice(&p.to_le_bytes());
+        match VarArchive::new_with_password(Cursor::new(archive_bytes), "hunter2") {
+            Err(VarError::InvalidKeyDerivation) => {},
+            _ => panic!("Expected an invalid key derivation error!")
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_not_password_protected() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_encrypted_archive("bsd.md", Compression::default())?;
+    match VarArchive::new_with_password(Cursor::new(archive_bytes), "hunter2") {
+        Err(VarError::NotPasswordProtected) => {},
+        _ => panic!("Expected a not password protected error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_unsupported() -> Result<(), Box<dyn Error>> {
+    let mut writer = VarWriter::new_with_key(Cursor::new(vec![]), KEY)?;
+    // Reserved encryption types have to be rejected up front, not once a file gets written
+    for encryption in [Encryption::Blowfish, Encryption::Twofish, Encryption::Unknown] {
+        match writer.set_encryption(encryption.clone()) {
+            Err(VarError::UnsupportedEncryption(unsupported)) => assert_eq!(unsupported, encryption),
+            _ => panic!("Expected an unsupported encryption error!")
+        }
+    }
+    Ok(())
+}
+
+/// Writes the given test file into an in-memory archive, using authenticated encryption
+fn write_gcm_archive(name: &str, compression: Compression) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new_with_key(&mut archive_bytes, KEY)?;
+        writer.set_encryption(Encryption::AESGCM)?;
+        let mut var_file = writer.write_file(name, compression)?;
+        let mut input_file = File::open(format!("tests/files/{}", name))?;
+        std::io::copy(&mut input_file, &mut var_file)?;
+    }
+    Ok(archive_bytes.into_inner())
+}
+
+#[test]
+fn test_encryption_gcm() -> Result<(), Box<dyn Error>> {
+    for compression in [Compression::None, Compression::ZStandard] {
+        let archive_bytes = write_gcm_archive("sales_records.csv", compression)?;
+        let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), KEY)?;
+        assert_eq!(archive.get_file_info("sales_records.csv")?.encryption, Encryption::AESGCM);
+        let mut file_content = vec![];
+        archive.get_file("sales_records.csv")?.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read("tests/files/sales_records.csv")?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_seek() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/sales_records.csv")?;
+    let aligned_size = 2 * GCM_CHUNK_SIZE as usize;
+    let options = [
+        VarFileOptions::new(Compression::None),
+        VarFileOptions::new(Compression::ZStandard).with_frame_size(64 * 1024)
+    ];
+    for encryption in [Encryption::AES, Encryption::AESGCM].iter() {
+        for file_options in options.iter() {
+            let mut archive_bytes = Cursor::new(vec![]);
+            {
+                let mut writer = VarWriter::new_with_key(&mut archive_bytes, KEY)?;
+                let file_options = file_options.clone().with_encryption(encryption.clone());
+                let mut var_file = writer.write_file_with_options("sales_records.csv", file_options.clone())?;
+                var_file.write_all(&input)?;
+                var_file.finish()?;
+                let mut var_file = writer.write_file_with_options("aligned.csv", file_options)?;
+                var_file.write_all(&input[..aligned_size])?;
+            }
+            let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes.into_inner()), KEY)?;
+            let mut var_file = archive.get_file("sales_records.csv")?;
+            // Offsets off the AES block and GCM chunk boundaries
+            let mut buffer = [0u8; 777];
+            for offset in [300_007, 65_535, 131_073, 17].iter() {
+                var_file.seek(SeekFrom::Start(*offset as u64))?;
+                var_file.read_exact(&mut buffer)?;
+                assert_eq!(&buffer[..], &input[*offset..*offset + buffer.len()]);
+            }
+            // Offsets on the GCM chunk boundaries, up to the end of data filling its last chunk
+            let mut var_file = archive.get_file("aligned.csv")?;
+            for offset in [aligned_size, GCM_CHUNK_SIZE as usize, 0].iter() {
+                assert_eq!(var_file.seek(SeekFrom::Start(*offset as u64))?, *offset as u64);
+                let mut file_content = vec![];
+                var_file.read_to_end(&mut file_content)?;
+                assert_eq!(&file_content[..], &input[*offset..aligned_size]);
+            }
+            assert_eq!(var_file.seek(SeekFrom::End(0))?, aligned_size as u64);
+            assert_eq!(var_file.read(&mut buffer)?, 0);
+            assert!(archive.read_range("aligned.csv", aligned_size as u64..aligned_size as u64)?.is_empty());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_gcm_tampered() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_gcm_archive("sales_records.csv", Compression::None)?;
+    // Flip a byte within the first 64KiB chunk, behind the entry's file header
+    archive_bytes[1000] ^= 0xFF;
+    let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), KEY)?;
+    let mut var_file = archive.get_file("sales_records.csv")?;
+    let mut buffer = [0u8; 16];
+    let error = var_file.read(&mut buffer).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn test_encryption_gcm_truncated() -> Result<(), Box<dyn Error>> {
+    let content = std::fs::read("tests/files/sales_records.csv")?;
+    let mut encrypted = vec![];
+    {
+        let mut gcm_writer = GcmWriter::new(&mut encrypted, &KEY)?;
+        gcm_writer.write_all(&content)?;
+        gcm_writer.finish()?;
+    }
+    // Drop the last chunk
+    let last_chunk_size = (content.len() % (64 * 1024)) + 16;
+    let truncated_length = (encrypted.len() - last_chunk_size) as u64;
+    let mut gcm_reader = GcmReader::new(Cursor::new(&encrypted).take(truncated_length), &KEY)?;
+    let mut decrypted = vec![];
+    let error = gcm_reader.read_to_end(&mut decrypted).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    // The complete data still decrypts fine
+    let mut gcm_reader = GcmReader::new(Cursor::new(&encrypted).take(encrypted.len() as u64), &KEY)?;
+    let mut decrypted = vec![];
+    gcm_reader.read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, content);
+    Ok(())
+}
+
+/// Writes the test files into an in-memory archive with an encrypted index
+fn write_index_encrypted_archive() -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new_with_key(&mut archive_bytes, KEY)?;
+        writer.set_index_encryption(true)?;
+        for name in ["bsd.md", "sales_records.csv"].iter() {
+            let mut var_file = writer.write_file(name, Compression::default())?;
+            let mut input_file = File::open(format!("tests/files/{}", name))?;
+            std::io::copy(&mut input_file, &mut var_file)?;
+        }
+    }
+    Ok(archive_bytes.into_inner())
+}
+
+#[test]
+fn test_encryption_index() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_index_encrypted_archive()?;
+    // Neither file name may show up in the archive
+    assert!(archive_bytes.windows(6).all(|window| window != b"bsd.md"));
+    assert!(archive_bytes.windows(13).all(|window| window != b"sales_records"));
+    let mut archive = VarArchive::new_with_key(Cursor::new(archive_bytes), KEY)?;
+    assert!(archive.is_index_encrypted());
+    let mut file_list = archive.get_file_list();
+    file_list.sort();
+    assert_eq!(file_list, vec!["bsd.md", "sales_records.csv"]);
+    let mut file_content = vec![];
+    archive.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    assert!(archive.verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_index_missing_key() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_index_encrypted_archive()?;
+    match VarArchive::new(Cursor::new(archive_bytes.clone())) {
+        Err(VarError::KeyRequired) => {},
+        _ => panic!("Expected a key required error!")
+    }
+    match VarArchive::new_with_key(Cursor::new(archive_bytes), [0u8; 32]) {
+        Err(VarError::WrongKey) => {},
+        _ => panic!("Expected a wrong key error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_index_stream() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_index_encrypted_archive()?;
+    let mut reader = VarStreamReader::new(&archive_bytes[..])?;
+    match reader.next_entry() {
+        Err(VarError::KeyRequired) => {},
+        _ => panic!("Expected a key required error!")
+    }
+    let mut reader = VarStreamReader::new_with_key(&archive_bytes[..], KEY)?;
+    let mut file = reader.next_entry()?.expect("Expected bsd.md!");
+    assert_eq!(file.get_file_info().filename, "bsd.md");
+    let mut file_content = vec![];
+    file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    let file = reader.next_entry()?.expect("Expected sales_records.csv!");
+    assert_eq!(file.get_file_info().filename, "sales_records.csv");
+    assert!(reader.next_entry()?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_index_requires_key() -> Result<(), Box<dyn Error>> {
+    let mut writer = VarWriter::new(Cursor::new(vec![]))?;
+    match writer.set_index_encryption(true) {
+        Err(VarError::KeyRequired) => {},
+        _ => panic!("Expected a key required error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_encryption_upgrade_keeps_encryption() -> Result<(), Box<dyn Error>> {
+    let key_derivation = KeyDerivation::scrypt(4, 8, 1);
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new_with_key_derivation(&mut archive_bytes, "hunter2", key_derivation.clone())?;
+        writer.set_index_encryption(true)?;
+        for name in ["bsd.md", "sales_records.csv"].iter() {
+            let mut var_file = writer.write_file(name, Compression::LZ4)?;
+            var_file.write_all(&std::fs::read(format!("tests/files/{}", name))?)?;
+        }
+    }
+    let mut archive = VarArchive::new_with_password(Cursor::new(archive_bytes.into_inner()), "hunter2")?;
+    let mut upgraded_bytes = Cursor::new(vec![]);
+    upgrade(&mut archive, &mut upgraded_bytes)?;
+    let mut upgraded = VarArchive::new_with_password(Cursor::new(upgraded_bytes.into_inner()), "hunter2")?;
+    assert!(upgraded.is_index_encrypted());
+    assert_eq!(upgraded.get_key_derivation(), Some(&key_derivation));
+    for name in ["bsd.md", "sales_records.csv"].iter() {
+        assert!(upgraded.get_file_info(name)?.is_encrypted());
+        let mut file_content = vec![];
+        upgraded.get_file(name)?.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read(format!("tests/files/{}", name))?);
+    }
+    Ok(())
+}
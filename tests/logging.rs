@@ -0,0 +1,88 @@
+extern crate vapor_archive as var;
+
+use var::{
+    VarWriter,
+    VarArchive,
+    Compression
+};
+
+use std::{
+    io::{
+        Write,
+        Cursor
+    },
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{
+            AtomicU64,
+            Ordering
+        }
+    },
+    error::Error
+};
+
+use tracing::{
+    span::{
+        Attributes,
+        Id,
+        Record
+    },
+    Event,
+    Metadata,
+    Subscriber
+};
+
+/// A subscriber recording the names of all spans and the number of events
+#[derive(Default)]
+struct RecordingSubscriber {
+    spans: Arc<Mutex<Vec<String>>>,
+    events: Arc<AtomicU64>,
+    next_id: AtomicU64
+}
+
+impl Subscriber for RecordingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.spans.lock().unwrap().push(String::from(span.metadata().name()));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {
+        self.events.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn test_logging_spans() -> Result<(), Box<dyn Error>> {
+    let subscriber = RecordingSubscriber::default();
+    let spans = subscriber.spans.clone();
+    let events = subscriber.events.clone();
+    tracing::subscriber::with_default(subscriber, || -> Result<(), Box<dyn Error>> {
+        let mut archive_bytes = Cursor::new(vec![]);
+        {
+            let mut writer = VarWriter::new(&mut archive_bytes)?;
+            let mut var_file = writer.write_file("bsd.md", Compression::default())?;
+            var_file.write_all(&std::fs::read("tests/files/bsd.md")?)?;
+        }
+        VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+        Ok(())
+    })?;
+    let spans = spans.lock().unwrap();
+    for name in ["write_file", "write_directory", "open_archive"].iter() {
+        assert!(spans.iter().any(|span| span == name), "Missing span {}", name);
+    }
+    assert!(events.load(Ordering::SeqCst) >= 4);
+    Ok(())
+}
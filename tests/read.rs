@@ -1,8 +1,18 @@
 use std::{
     io::{
         Read,
-        Write
+        Write,
+        Seek,
+        SeekFrom,
+        Cursor,
+        ErrorKind,
+        Result as IoResult,
+        Error as IoError
     },
+    rc::Rc,
+    borrow::Cow,
+    sync::OnceLock,
+    cell::Cell,
     fs::{
         File
     },
@@ -13,9 +23,65 @@ use std::{
 extern crate vapor_archive as var;
 
 use var::{
-    VarArchive
+    VarArchive,
+    VarSharedArchive,
+    VarMappedArchive,
+    VarSliceArchive,
+    VarStreamReader,
+    VarWriter,
+    VarFileOptions,
+    Compression,
+    read::verify::FailureKind,
+    write::upgrade::upgrade,
+    shared::{
+        archive_header::ArchiveHeader,
+        file_header::FileHeader,
+        frame::ENTRY_FRAME_SIZE,
+        format_version::{
+            FormatVersion,
+            FORMAT_VERSION_SIZE
+        },
+        compression::{
+            CompressionParams,
+            DEFAULT_FRAME_SIZE
+        },
+        directory::Directory,
+        trailer::{
+            Trailer,
+            TRAILER_SIZE
+        },
+        encoding::{
+            Encode,
+            Decode
+        },
+        error::Error as VarError
+    }
 };
 
+/// Writes the given test file into an in-memory archive
+fn write_archive(name: &str, compression: Compression) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        let mut var_file = writer.write_file(name, compression)?;
+        let mut input_file = File::open(PathBuf::from("tests/files").join(name))?;
+        std::io::copy(&mut input_file, &mut var_file)?;
+    }
+    Ok(archive_bytes.into_inner())
+}
+
+/// Writes the given test file with the given compression and checks it reads back unchanged
+fn assert_roundtrip(name: &str, compression: Compression) -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive(name, compression.clone())?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    assert_eq!(archive.get_file_info(name)?.compression, compression);
+    let mut archive_file = archive.get_file(name)?;
+    let mut file_content = vec![];
+    archive_file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read(PathBuf::from("tests/files").join(name))?);
+    Ok(())
+}
+
 #[test]
 fn test_reader_image_compressed() -> Result<(), Box<dyn Error>> {
     let file = File::open("tests/out/image_c.var")?;
@@ -182,4 +248,804 @@ fn test_reader_mixed_uncompressed() -> Result<(), Box<dyn Error>> {
         std::io::copy(&mut var_file, &mut out_file)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_reader_raw_checksum() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::default())?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let mut archive_file = archive.get_file("bsd.md")?;
+    let mut file_content = vec![];
+    archive_file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    Ok(())
+}
+
+#[test]
+fn test_reader_raw_checksum_mismatch() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    // Flip a byte within the stored data
+    let content = std::fs::read("tests/files/bsd.md")?;
+    let data_start = archive_bytes.windows(content.len())
+        .position(|window| window == &content[..])
+        .unwrap();
+    archive_bytes[data_start + 100] ^= 0xFF;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let mut archive_file = archive.get_file("bsd.md")?;
+    let mut file_content = vec![];
+    let error = archive_file.read_to_end(&mut file_content).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn test_reader_data_checksum_mismatch() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    let content = std::fs::read("tests/files/bsd.md")?;
+    let data_start = archive_bytes.windows(content.len())
+        .position(|window| window == &content[..])
+        .unwrap();
+    archive_bytes[data_start + 100] ^= 0xFF;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?
+        .with_data_verification(true);
+    match archive.get_file("bsd.md") {
+        Err(var::shared::error::Error::DataChecksumMismatch(name)) => assert_eq!(name, "bsd.md"),
+        _ => panic!("Expected a data checksum mismatch!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_verify() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("sales_records.csv", Compression::default())?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let report = archive.verify()?;
+    assert!(report.is_ok());
+    assert_eq!(report.entries_checked, 1);
+    Ok(())
+}
+
+#[test]
+fn test_reader_verify_corrupt() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    let content = std::fs::read("tests/files/bsd.md")?;
+    let data_start = archive_bytes.windows(content.len())
+        .position(|window| window == &content[..])
+        .unwrap();
+    archive_bytes[data_start + 100] ^= 0xFF;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let report = archive.verify()?;
+    assert!(!report.is_ok());
+    assert!(report.to_string().contains("bsd.md"));
+    let failures: Vec<FailureKind> = report.failures.into_iter()
+        .map(|failure| failure.kind)
+        .collect();
+    assert_eq!(failures, vec![FailureKind::DataChecksumMismatch, FailureKind::RawChecksumMismatch]);
+    Ok(())
+}
+
+#[test]
+fn test_reader_verify_nested_overlap() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    let trailer_begin = archive_bytes.len() - TRAILER_SIZE as usize;
+    let trailer = Trailer::decode(&mut &archive_bytes[trailer_begin..])?;
+    let directory_begin = trailer.directory_range.start as usize;
+    let mut directory = Directory::decode(&mut &archive_bytes[directory_begin..])?;
+    // Point two more entries into the data of the first one, neither overlapping the other
+    let data_begin = directory.get_file("bsd.md").unwrap().end;
+    directory.set_file("first", data_begin + 10..data_begin + 20);
+    directory.set_file("second", data_begin + 30..data_begin + 40);
+    let directory_begin = archive_bytes.len() as u64;
+    archive_bytes.extend(directory.to_bytes());
+    let directory_end = archive_bytes.len() as u64;
+    archive_bytes.extend(Trailer::new(directory_begin..directory_end).to_bytes());
+    let report = VarArchive::new(Cursor::new(archive_bytes))?.verify()?;
+    let overlapping: Vec<(String, FailureKind)> = report.failures.into_iter()
+        .filter(|failure| matches!(failure.kind, FailureKind::OverlappingRanges(_)))
+        .map(|failure| (failure.path, failure.kind))
+        .collect();
+    assert_eq!(overlapping, vec![
+        (String::from("first"), FailureKind::OverlappingRanges(String::from("bsd.md"))),
+        (String::from("second"), FailureKind::OverlappingRanges(String::from("bsd.md")))
+    ]);
+    Ok(())
+}
+
+#[test]
+fn test_reader_text_lz4() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("bsd.md", Compression::LZ4)
+}
+
+#[test]
+fn test_reader_csv_lz4() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("sales_records.csv", Compression::LZ4)
+}
+
+#[test]
+fn test_reader_text_lzma() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("bsd.md", Compression::LZMA)
+}
+
+#[test]
+fn test_reader_csv_lzma2() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("sales_records.csv", Compression::LZMA2)
+}
+
+#[test]
+fn test_reader_text_deflate() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("bsd.md", Compression::Deflate)
+}
+
+#[test]
+fn test_reader_csv_deflate() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("sales_records.csv", Compression::Deflate)
+}
+
+#[test]
+fn test_reader_text_bzip2() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("bsd.md", Compression::BZIP2)
+}
+
+#[test]
+fn test_reader_csv_bzip2() -> Result<(), Box<dyn Error>> {
+    assert_roundtrip("sales_records.csv", Compression::BZIP2)
+}
+
+#[test]
+fn test_reader_deflate_interop() -> Result<(), Box<dyn Error>> {
+    // The stored data has to be a plain raw deflate stream, so it can be copied into ZIP containers
+    let archive_bytes = write_archive("bsd.md", Compression::Deflate)?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.clone()))?;
+    let file_info = archive.get_file_info("bsd.md")?;
+    let data_size = file_info.data_size as usize;
+    // The data of the first entry follows the archive header, the entry frame and the file header
+    let file_header_size = FileHeader::default().with_file_info(file_info).to_bytes().len();
+    let data_begin = 3 + FORMAT_VERSION_SIZE + ArchiveHeader::default().to_bytes().len() + ENTRY_FRAME_SIZE as usize + file_header_size;
+    let data = &archive_bytes[data_begin..data_begin + data_size];
+    let mut file_content = vec![];
+    flate2::read::DeflateDecoder::new(data).read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    Ok(())
+}
+
+#[test]
+fn test_reader_lzma_preset() -> Result<(), Box<dyn Error>> {
+    let mut sizes = vec![];
+    for preset in [0, 9].iter() {
+        let mut archive_bytes = Cursor::new(vec![]);
+        {
+            let mut writer = VarWriter::new(&mut archive_bytes)?;
+            let mut var_file = writer.write_file_with_level("sales_records.csv", Compression::LZMA2, *preset)?;
+            let mut input_file = File::open("tests/files/sales_records.csv")?;
+            std::io::copy(&mut input_file, &mut var_file)?;
+        }
+        let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+        sizes.push(archive.get_file_info("sales_records.csv")?.data_size);
+        let mut archive_file = archive.get_file("sales_records.csv")?;
+        let mut file_content = vec![];
+        archive_file.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read("tests/files/sales_records.csv")?);
+    }
+    assert!(sizes[1] < sizes[0]);
+    Ok(())
+}
+
+#[test]
+fn test_reader_zstd_options() -> Result<(), Box<dyn Error>> {
+    let options = [
+        VarFileOptions::new(Compression::ZStandard).with_level(1),
+        VarFileOptions::new(Compression::ZStandard)
+            .with_level(19)
+            .with_window_log(24)
+            .with_long_distance_matching(true)
+    ];
+    for file_options in options.iter() {
+        let mut archive_bytes = Cursor::new(vec![]);
+        {
+            let mut writer = VarWriter::new(&mut archive_bytes)?;
+            let mut var_file = writer.write_file_with_options("sales_records.csv", file_options.clone())?;
+            let mut input_file = File::open("tests/files/sales_records.csv")?;
+            std::io::copy(&mut input_file, &mut var_file)?;
+        }
+        let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+        let file_info = archive.get_file_info("sales_records.csv")?;
+        assert_eq!(file_info.compression_params, file_options.compression_params);
+        let mut archive_file = archive.get_file("sales_records.csv")?;
+        let mut file_content = vec![];
+        archive_file.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read("tests/files/sales_records.csv")?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_zstd_multithreaded() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        writer.set_workers(4);
+        let mut var_file = writer.write_file("sales_records.csv", Compression::ZStandard)?;
+        let mut input_file = File::open("tests/files/sales_records.csv")?;
+        std::io::copy(&mut input_file, &mut var_file)?;
+    }
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+    assert!(archive.verify()?.is_ok());
+    let mut archive_file = archive.get_file("sales_records.csv")?;
+    let mut file_content = vec![];
+    archive_file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/sales_records.csv")?);
+    Ok(())
+}
+
+#[test]
+fn test_reader_default_params_recorded() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::ZStandard)?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let file_info = archive.get_file_info("bsd.md")?;
+    assert_eq!(file_info.compression_params, CompressionParams::default().with_level(9));
+    Ok(())
+}
+
+#[test]
+fn test_compression_codes() {
+    let codes = [
+        (Compression::None, 0),
+        (Compression::ZStandard, 1),
+        (Compression::Deflate, 2),
+        (Compression::LZMA, 3),
+        (Compression::LZMA2, 4),
+        (Compression::BZIP2, 5),
+        (Compression::LZ4, 6)
+    ];
+    for (compression, code) in codes.iter() {
+        assert_eq!(u8::from(compression.clone()), *code);
+        assert_eq!(Compression::from(*code), *compression);
+    }
+}
+
+#[test]
+fn test_reader_error_unsupported_compression() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::ZStandard)?;
+    // The compression code follows the data range, the raw size and the data size in the file header
+    let file_header_begin = 3 + FORMAT_VERSION_SIZE + ArchiveHeader::default().to_bytes().len() + ENTRY_FRAME_SIZE as usize;
+    let compression_offset = file_header_begin + 16 + 8 + 8;
+    assert_eq!(archive_bytes[compression_offset], 1);
+    archive_bytes[compression_offset] = 9;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.clone()))?;
+    match archive.get_file("bsd.md") {
+        Err(VarError::UnsupportedCompression(Compression::Unknown)) => {},
+        Err(other) => panic!("Unexpected error {:?}", other),
+        Ok(_) => panic!("Expected an unsupported compression error!")
+    }
+    let failures: Vec<FailureKind> = archive.verify()?.failures.into_iter()
+        .map(|failure| failure.kind)
+        .collect();
+    assert_eq!(failures, vec![FailureKind::Decompression]);
+    let mut stream_reader = VarStreamReader::new(&archive_bytes[..])?;
+    assert!(matches!(stream_reader.next_entry(), Err(VarError::UnsupportedCompression(Compression::Unknown))));
+    Ok(())
+}
+
+/// A reader that fails every read once it has been switched off
+struct FailingReader {
+    inner: Cursor<Vec<u8>>,
+    failing: Rc<Cell<bool>>
+}
+
+impl Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if self.failing.get() {
+            return Err(IoError::other("Device unplugged"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for FailingReader {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_reader_error_context() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::None)?;
+    let failing = Rc::new(Cell::new(false));
+    let reader = FailingReader {
+        inner: Cursor::new(archive_bytes),
+        failing: failing.clone()
+    };
+    let mut archive = VarArchive::new(reader)?;
+    failing.set(true);
+    let error = archive.get_file_info("bsd.md").unwrap_err();
+    match &error {
+        VarError::CantReadFileHeader { path, offset, source } => {
+            assert_eq!(path, "bsd.md");
+            assert!(*offset > 0);
+            assert_eq!(source.to_string(), "Device unplugged");
+        },
+        other => panic!("Unexpected error {:?}", other)
+    }
+    assert!(error.source().is_some());
+    assert!(error.to_string().contains("bsd.md"));
+    Ok(())
+}
+
+#[test]
+fn test_reader_error_corrupt_directory() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    // Claim far more entries than the directory holds
+    let trailer_begin = archive_bytes.len() - TRAILER_SIZE as usize;
+    let trailer = Trailer::decode(&mut &archive_bytes[trailer_begin..])?;
+    let directory_begin = trailer.directory_range.start as usize;
+    archive_bytes[directory_begin..directory_begin + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    match VarArchive::new(Cursor::new(archive_bytes)) {
+        Err(error @ VarError::CorruptDirectory { .. }) => assert!(error.source().is_some()),
+        Err(other) => panic!("Unexpected error {:?}", other),
+        Ok(_) => panic!("Expected a corrupt directory!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_error_corrupt_trailer() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+    // Cut the trailer short, as if the archive had been truncated
+    let length = archive_bytes.len();
+    archive_bytes.truncate(length - 4);
+    match VarArchive::new(Cursor::new(archive_bytes)) {
+        Err(error @ VarError::CorruptTrailer(_)) => assert!(error.source().is_some()),
+        Err(other) => panic!("Unexpected error {:?}", other),
+        Ok(_) => panic!("Expected a corrupt trailer!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_format_version() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::None)?;
+    assert_eq!(&archive_bytes[3..3 + FORMAT_VERSION_SIZE], &FormatVersion::CURRENT.to_bytes());
+    let archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    assert_eq!(archive.get_format_version(), FormatVersion::CURRENT);
+    Ok(())
+}
+
+#[test]
+fn test_reader_format_version_unsupported() -> Result<(), Box<dyn Error>> {
+    // Newer minor versions may add fields older readers can't skip
+    let newer_versions = [
+        FormatVersion::new(FormatVersion::CURRENT.major, FormatVersion::CURRENT.minor + 1),
+        FormatVersion::new(FormatVersion::CURRENT.major + 1, 0)
+    ];
+    for newer in newer_versions.iter() {
+        let mut archive_bytes = write_archive("bsd.md", Compression::None)?;
+        archive_bytes[3..3 + FORMAT_VERSION_SIZE].copy_from_slice(&newer.to_bytes());
+        match VarArchive::new(Cursor::new(archive_bytes.clone())) {
+            Err(VarError::VersionMismatch { found, supported }) => {
+                assert_eq!(found, *newer);
+                assert_eq!(supported, FormatVersion::CURRENT);
+            },
+            Err(other) => panic!("Unexpected error {:?}", other),
+            Ok(_) => panic!("Expected a version mismatch!")
+        }
+        assert!(matches!(VarStreamReader::new(&archive_bytes[..]), Err(VarError::VersionMismatch { .. })));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_revision_0() -> Result<(), Box<dyn Error>> {
+    // Written by the very first release, before the format version was stored
+    let mut archive = VarArchive::new(File::open("tests/files/revision_0.var")?)?;
+    assert_eq!(archive.get_format_version(), FormatVersion::REVISION_0);
+    assert_eq!(archive.get_file_list(), vec![String::from("bsd.md")]);
+    let file_info = archive.get_file_info("bsd.md")?;
+    assert_eq!(file_info.compression, Compression::ZStandard);
+    assert_eq!(file_info.compression_params.level, Some(9));
+    let mut file_content = vec![];
+    archive.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    assert!(archive.verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_reader_upgrade_revision_0() -> Result<(), Box<dyn Error>> {
+    let mut archive = VarArchive::new(File::open("tests/files/revision_0.var")?)?;
+    let old_info = archive.get_file_info("bsd.md")?;
+    let mut upgraded_bytes = Cursor::new(vec![]);
+    upgrade(&mut archive, &mut upgraded_bytes)?;
+    let mut upgraded = VarArchive::new(Cursor::new(upgraded_bytes.into_inner()))?;
+    assert_eq!(upgraded.get_format_version(), FormatVersion::CURRENT);
+    // The entry data is copied, not recompressed
+    let new_info = upgraded.get_file_info("bsd.md")?;
+    assert_eq!(new_info.data_checksum, old_info.data_checksum);
+    assert_eq!(new_info.data_size, old_info.data_size);
+    let mut file_content = vec![];
+    upgraded.get_file("bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    assert!(upgraded.verify()?.is_ok());
+    Ok(())
+}
+
+/// A source that can only be read front to back, like a pipe
+struct StreamSource<R: Read> {
+    inner: R
+}
+
+impl<R: Read> Read for StreamSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        self.inner.read(buf)
+    }
+}
+
+#[test]
+fn test_reader_stream() -> Result<(), Box<dyn Error>> {
+    let entries = [
+        ("bsd.md", Compression::ZStandard),
+        ("sales_records.csv", Compression::LZ4),
+        ("copy/bsd.md", Compression::None)
+    ];
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        for (path, compression) in entries.iter() {
+            let mut var_file = writer.write_file(path, compression.clone())?;
+            let name = path.rsplit('/').next().unwrap();
+            var_file.write_all(&std::fs::read(PathBuf::from("tests/files").join(name))?)?;
+        }
+    }
+    let source = StreamSource { inner: Cursor::new(archive_bytes.into_inner()) };
+    let mut reader = VarStreamReader::new(source)?;
+    assert_eq!(reader.get_format_version(), FormatVersion::CURRENT);
+    // Entries come in the order they were written, partially read ones get skipped
+    let mut file = reader.next_entry()?.expect("Expected bsd.md!");
+    assert_eq!(file.get_file_info().filename, "bsd.md");
+    let mut file_content = vec![];
+    file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    let mut file = reader.next_entry()?.expect("Expected sales_records.csv!");
+    assert_eq!(file.get_file_info().filename, "sales_records.csv");
+    assert_eq!(file.get_file_info().compression, Compression::LZ4);
+    file.read_exact(&mut [0u8; 16])?;
+    let mut file = reader.next_entry()?.expect("Expected copy/bsd.md!");
+    assert_eq!(file.get_file_info().filename, "copy/bsd.md");
+    let mut file_content = vec![];
+    file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, std::fs::read("tests/files/bsd.md")?);
+    assert!(reader.next_entry()?.is_none());
+    assert!(reader.next_entry()?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_reader_stream_corrupt_frame() -> Result<(), Box<dyn Error>> {
+    let mut archive_bytes = write_archive("bsd.md", Compression::ZStandard)?;
+    let frame_begin = 3 + FORMAT_VERSION_SIZE + ArchiveHeader::default().to_bytes().len();
+    archive_bytes[frame_begin] = b'X';
+    let mut reader = VarStreamReader::new(StreamSource { inner: &archive_bytes[..] })?;
+    match reader.next_entry() {
+        Err(VarError::CorruptEntryFrame { offset, .. }) => assert_eq!(offset, frame_begin as u64),
+        _ => panic!("Expected a corrupt entry frame error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_stream_not_streamable() -> Result<(), Box<dyn Error>> {
+    // Entries of revision 0 archives have no framing, so their data can't be found without the directory
+    match VarStreamReader::new(File::open("tests/files/revision_0.var")?) {
+        Err(VarError::NotStreamable(format_version)) => assert_eq!(format_version, FormatVersion::REVISION_0),
+        _ => panic!("Expected a not streamable error!")
+    }
+    Ok(())
+}
+
+/// Writes sales_records.csv using the given options and checks seeking within it
+fn assert_seek(options: VarFileOptions) -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/sales_records.csv")?;
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        let mut var_file = writer.write_file_with_options("sales_records.csv", options)?;
+        var_file.write_all(&input)?;
+    }
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+    let mut archive_file = archive.get_file("sales_records.csv")?;
+    let mut buffer = [0u8; 1000];
+    for offset in [700_000, 12_345, 1_000_000, 999_999, 0].iter() {
+        assert_eq!(archive_file.seek(SeekFrom::Start(*offset as u64))?, *offset as u64);
+        archive_file.read_exact(&mut buffer)?;
+        assert_eq!(&buffer[..], &input[*offset..*offset + buffer.len()]);
+    }
+    assert_eq!(archive_file.seek(SeekFrom::Current(-500))?, 500);
+    archive_file.read_exact(&mut buffer)?;
+    assert_eq!(&buffer[..], &input[500..1500]);
+    assert_eq!(archive_file.seek(SeekFrom::End(-100))?, input.len() as u64 - 100);
+    let mut file_content = vec![];
+    archive_file.read_to_end(&mut file_content)?;
+    assert_eq!(&file_content[..], &input[input.len() - 100..]);
+    match archive_file.seek(SeekFrom::Current(-(input.len() as i64) - 1)) {
+        Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidInput),
+        Ok(_) => panic!("Expected an invalid seek!")
+    }
+    // Reading from the start verifies the raw checksum again
+    archive_file.seek(SeekFrom::Start(0))?;
+    let mut file_content = vec![];
+    archive_file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, input);
+    Ok(())
+}
+
+#[test]
+fn test_reader_seek_uncompressed() -> Result<(), Box<dyn Error>> {
+    assert_seek(VarFileOptions::new(Compression::None))
+}
+
+#[test]
+fn test_reader_seek_frames() -> Result<(), Box<dyn Error>> {
+    for compression in [Compression::ZStandard, Compression::LZ4, Compression::Deflate].iter() {
+        assert_seek(VarFileOptions::new(compression.clone()).with_frame_size(64 * 1024))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_seek_without_frames() -> Result<(), Box<dyn Error>> {
+    // Seeking backwards decompresses from the start
+    assert_seek(VarFileOptions::new(Compression::ZStandard).without_frames())
+}
+
+#[test]
+fn test_reader_seek_default_frames() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/sales_records.csv")?;
+    assert!(input.len() as u64 > DEFAULT_FRAME_SIZE);
+    let compressions = [Compression::ZStandard, Compression::LZ4, Compression::Deflate, Compression::None];
+    for compression in compressions.iter() {
+        let mut archive_bytes = Cursor::new(vec![]);
+        {
+            let mut writer = VarWriter::new(&mut archive_bytes)?;
+            let mut var_file = writer.write_file("sales_records.csv", compression.clone())?;
+            var_file.write_all(&input)?;
+        }
+        let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+        let file_info = archive.get_file_info("sales_records.csv")?;
+        match compression {
+            // Uncompressed and raw deflate data stays a single stream
+            Compression::None | Compression::Deflate => assert!(file_info.seek_table.is_none()),
+            _ => {
+                let seek_table = file_info.seek_table.expect("Expected a seek table by default!");
+                assert_eq!(seek_table.frame_size, DEFAULT_FRAME_SIZE);
+                assert_eq!(seek_table.get_frame_count(), input.len().div_ceil(DEFAULT_FRAME_SIZE as usize));
+            }
+        }
+    }
+    assert_seek(VarFileOptions::new(Compression::ZStandard))?;
+    assert_seek(VarFileOptions::default())
+}
+
+#[test]
+fn test_reader_frames() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/sales_records.csv")?;
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        let options = VarFileOptions::new(Compression::LZ4).with_frame_size(100_000);
+        let mut var_file = writer.write_file_with_options("sales_records.csv", options)?;
+        var_file.write_all(&input)?;
+        let file_info = var_file.finish()?;
+        let seek_table = file_info.seek_table.expect("Expected a seek table!");
+        assert_eq!(seek_table.get_frame_count(), input.len().div_ceil(100_000));
+        assert_eq!(seek_table.frame_ends.last(), Some(&file_info.data_size));
+    }
+    let archive_bytes = archive_bytes.into_inner();
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.clone()))?;
+    assert!(archive.verify()?.is_ok());
+    // Frames get decompressed one after another when reading sequentially
+    let mut reader = VarStreamReader::new(StreamSource { inner: &archive_bytes[..] })?;
+    let mut file = reader.next_entry()?.expect("Expected sales_records.csv!");
+    let mut file_content = vec![];
+    file.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, input);
+    Ok(())
+}
+
+/// A reader that counts the bytes read from it
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    bytes_read: Rc<Cell<u64>>
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_reader_read_range() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/sales_records.csv")?;
+    let options = [
+        VarFileOptions::new(Compression::None),
+        VarFileOptions::new(Compression::ZStandard).with_frame_size(64 * 1024)
+    ];
+    for file_options in options.iter() {
+        let mut archive_bytes = Cursor::new(vec![]);
+        {
+            let mut writer = VarWriter::new(&mut archive_bytes)?;
+            let mut var_file = writer.write_file_with_options("sales_records.csv", file_options.clone())?;
+            var_file.write_all(&input)?;
+        }
+        let bytes_read = Rc::new(Cell::new(0));
+        let reader = CountingReader {
+            inner: Cursor::new(archive_bytes.into_inner()),
+            bytes_read: bytes_read.clone()
+        };
+        let mut archive = VarArchive::new(reader)?;
+        let data_size = archive.get_file_info("sales_records.csv")?.data_size;
+        bytes_read.set(0);
+        let range = archive.read_range("sales_records.csv", 1_000_000..1_001_000)?;
+        assert_eq!(range, &input[1_000_000..1_001_000]);
+        // Only the file header and the frames covering the range get read
+        assert!(bytes_read.get() < data_size / 4);
+        let range = archive.read_range("sales_records.csv", 0..input.len() as u64)?;
+        assert_eq!(range, input);
+        assert!(archive.read_range("sales_records.csv", 5..5)?.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_read_range_out_of_bounds() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::ZStandard)?;
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes))?;
+    let raw_size = archive.get_file_info("bsd.md")?.raw_size;
+    match archive.read_range("bsd.md", 1000..raw_size + 1) {
+        Err(VarError::InvalidRange { range, .. }) => assert_eq!(range, 1000..raw_size + 1),
+        _ => panic!("Expected an invalid range error!")
+    }
+    match archive.read_range("missing.md", 0..1) {
+        Err(VarError::FileNotFound(_)) => {},
+        _ => panic!("Expected a file not found error!")
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reader_shared() -> Result<(), Box<dyn Error>> {
+    fn assert_sync<T: Sync>(_: &T) {}
+    let paths = ["bsd.md", "sales_records.csv", "lz4/bsd.md", "framed/sales_records.csv"];
+    {
+        let mut writer = VarWriter::new(File::create("tests/out/shared.var")?)?;
+        for path in paths.iter() {
+            let name = path.rsplit('/').next().unwrap();
+            let mut options = VarFileOptions::new(Compression::ZStandard);
+            if path.starts_with("lz4/") {
+                options = VarFileOptions::new(Compression::LZ4);
+            } else if path.starts_with("framed/") {
+                options = options.with_frame_size(64 * 1024);
+            }
+            let mut var_file = writer.write_file_with_options(path, options)?;
+            var_file.write_all(&std::fs::read(PathBuf::from("tests/files").join(name))?)?;
+        }
+        writer.finish()?;
+    }
+    let archive = VarSharedArchive::new(File::open("tests/out/shared.var")?)?;
+    assert_sync(&archive);
+    // Every thread streams a different entry through the same open file
+    std::thread::scope(|scope| {
+        for path in paths.iter() {
+            let archive = &archive;
+            scope.spawn(move || {
+                let name = path.rsplit('/').next().unwrap();
+                let input = std::fs::read(PathBuf::from("tests/files").join(name)).unwrap();
+                for _ in 0..3 {
+                    let mut reader = archive.get_reader();
+                    let mut file_content = vec![];
+                    reader.get_file(path).unwrap().read_to_end(&mut file_content).unwrap();
+                    assert_eq!(file_content, input);
+                    assert_eq!(archive.read_range(path, 100..200).unwrap(), &input[100..200]);
+                }
+            });
+        }
+    });
+    let mut file_list = archive.get_file_list();
+    file_list.sort();
+    assert_eq!(file_list, vec!["bsd.md", "framed/sales_records.csv", "lz4/bsd.md", "sales_records.csv"]);
+    // Readers keep their own position, so files of different readers can be read interleaved
+    let mut first_reader = archive.get_reader();
+    let mut second_reader = archive.get_reader();
+    let mut first_file = first_reader.get_file("bsd.md")?;
+    let mut second_file = second_reader.get_file("sales_records.csv")?;
+    let mut first_buffer = [0u8; 100];
+    let mut second_buffer = [0u8; 100];
+    first_file.read_exact(&mut first_buffer)?;
+    second_file.read_exact(&mut second_buffer)?;
+    first_file.read_exact(&mut first_buffer)?;
+    assert_eq!(&first_buffer[..], &std::fs::read("tests/files/bsd.md")?[100..200]);
+    assert_eq!(&second_buffer[..], &std::fs::read("tests/files/sales_records.csv")?[0..100]);
+    Ok(())
+}
+
+#[test]
+fn test_reader_shared_in_memory() -> Result<(), Box<dyn Error>> {
+    let archive_bytes = write_archive("bsd.md", Compression::LZ4)?;
+    let archive = VarSharedArchive::new(archive_bytes)?;
+    assert_eq!(archive.get_file_info("bsd.md")?.compression, Compression::LZ4);
+    assert!(archive.get_reader().verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_reader_mapped() -> Result<(), Box<dyn Error>> {
+    fn assert_sync<T: Sync>(_: &T) {}
+    {
+        let mut writer = VarWriter::new(File::create("tests/out/mapped.var")?)?;
+        writer.write_file("bsd.md", Compression::None)?
+            .write_all(&std::fs::read("tests/files/bsd.md")?)?;
+        writer.write_file("sales_records.csv", Compression::ZStandard)?
+            .write_all(&std::fs::read("tests/files/sales_records.csv")?)?;
+        writer.finish()?;
+    }
+    let archive = VarMappedArchive::new(&File::open("tests/out/mapped.var")?)?.with_data_verification(true);
+    assert_sync(&archive);
+    // Stored files are borrowed straight from the mapping, so every access yields the same bytes
+    let stored = archive.get_stored("bsd.md")?;
+    assert_eq!(stored, &std::fs::read("tests/files/bsd.md")?[..]);
+    assert_eq!(archive.get_stored("bsd.md")?.as_ptr(), stored.as_ptr());
+    assert!(matches!(archive.get_data("bsd.md")?, Cow::Borrowed(data) if data.as_ptr() == stored.as_ptr()));
+    // Compressed files can't be borrowed, but get decompressed from the mapping
+    assert!(matches!(archive.get_stored("sales_records.csv"), Err(VarError::NotStored(path)) if path == "sales_records.csv"));
+    let data = archive.get_data("sales_records.csv")?;
+    assert!(matches!(data, Cow::Owned(_)));
+    assert_eq!(&data[..], &std::fs::read("tests/files/sales_records.csv")?[..]);
+    assert_eq!(archive.read_range("sales_records.csv", 100..200)?, &data[100..200]);
+    assert!(matches!(archive.get_data("missing.md"), Err(VarError::FileNotFound(_))));
+    assert!(archive.get_reader().verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_reader_slice() -> Result<(), Box<dyn Error>> {
+    static ARCHIVE: OnceLock<VarSliceArchive<'static>> = OnceLock::new();
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        writer.write_file("bsd.md", Compression::None)?
+            .write_all(&std::fs::read("tests/files/bsd.md")?)?;
+        writer.write_file("lz4/bsd.md", Compression::LZ4)?
+            .write_all(&std::fs::read("tests/files/bsd.md")?)?;
+    }
+    // Stands in for bytes embedded with include_bytes!
+    let archive_bytes: &'static [u8] = Box::leak(archive_bytes.into_inner().into_boxed_slice());
+    let archive = ARCHIVE.get_or_init(|| VarSliceArchive::new(archive_bytes).unwrap());
+    let input = std::fs::read("tests/files/bsd.md")?;
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                // Stored files outlive the archive handle, since they borrow from the static bytes
+                let stored: &'static [u8] = ARCHIVE.get().unwrap().get_stored("bsd.md").unwrap();
+                assert_eq!(stored, &input[..]);
+                assert!(archive_bytes.as_ptr_range().contains(&stored.as_ptr()));
+                let data = ARCHIVE.get().unwrap().get_data("lz4/bsd.md").unwrap();
+                assert!(matches!(data, Cow::Owned(_)));
+                assert_eq!(&data[..], &input[..]);
+            });
+        }
+    });
+    assert!(matches!(archive.get_data("bsd.md")?, Cow::Borrowed(_)));
+    assert!(matches!(archive.get_stored("lz4/bsd.md"), Err(VarError::NotStored(_))));
+    assert_eq!(archive.read_range("lz4/bsd.md", 100..200)?, &input[100..200]);
+    let mut file_content = vec![];
+    archive.get_reader().get_file("lz4/bsd.md")?.read_to_end(&mut file_content)?;
+    assert_eq!(file_content, input);
+    Ok(())
+}
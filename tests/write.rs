@@ -6,7 +6,17 @@ use var::{
         Digest
     },
     VarWriter,
-    Compression
+    VarArchive,
+    VarFileOptions,
+    Compression,
+    write::spool::{
+        Spool,
+        SPOOL_MEMORY_LIMIT
+    },
+    shared::{
+        trailer::TRAILER_SIZE,
+        error::Error as VarError
+    }
 };
 
 use std::{
@@ -15,7 +25,13 @@ use std::{
     },
     io::{
         Read,
-        Write
+        Write,
+        Seek,
+        SeekFrom,
+        Cursor,
+        ErrorKind,
+        Result as IoResult,
+        Error as IoError
     },
     error::Error
 };
@@ -60,6 +76,59 @@ fn test_writer_text_uncompressed() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_writer_text_lz4() -> Result<(), Box<dyn Error>> {
+    let file = File::create("tests/out/text_lz4.var")?;
+    let mut writer = VarWriter::new(file)?;
+    let mut var_file = writer.write_file("bsd.md", Compression::LZ4)?;
+    let mut input_file = File::open("tests/files/bsd.md")?;
+    std::io::copy(&mut input_file, &mut var_file)?;
+    Ok(())
+}
+
+#[test]
+fn test_writer_csv_lzma2() -> Result<(), Box<dyn Error>> {
+    let file = File::create("tests/out/csv_lzma2.var")?;
+    let mut writer = VarWriter::new(file)?;
+    let mut var_file = writer.write_file("sales_records.csv", Compression::LZMA2)?;
+    let mut input_file = File::open("tests/files/sales_records.csv")?;
+    std::io::copy(&mut input_file, &mut var_file)?;
+    Ok(())
+}
+
+#[test]
+fn test_writer_lzma_invalid_preset() -> Result<(), Box<dyn Error>> {
+    let mut writer = VarWriter::new(Cursor::new(vec![]))?;
+    for compression in [Compression::LZMA, Compression::LZMA2] {
+        match writer.write_file_with_level("sales_records.csv", compression, 10) {
+            Err(VarError::CantWriteFile { path, source }) => {
+                assert_eq!(path, "sales_records.csv");
+                assert_eq!(source.kind(), ErrorKind::InvalidInput);
+            },
+            Err(other) => panic!("Unexpected error {:?}", other),
+            Ok(_) => panic!("Expected an invalid preset error!")
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_writer_zstd_invalid_window_log() -> Result<(), Box<dyn Error>> {
+    let mut writer = VarWriter::new(Cursor::new(vec![]))?;
+    for frame_size_opt in [None, Some(1024)] {
+        let mut options = VarFileOptions::new(Compression::ZStandard).with_window_log(40);
+        if let Some(frame_size) = frame_size_opt {
+            options = options.with_frame_size(frame_size);
+        }
+        match writer.write_file_with_options("sales_records.csv", options) {
+            Err(VarError::CantWriteFile { source, .. }) => assert_eq!(source.kind(), ErrorKind::InvalidInput),
+            Err(other) => panic!("Unexpected error {:?}", other),
+            Ok(_) => panic!("Expected an invalid window log error!")
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn test_writer_csv_compressed() -> Result<(), Box<dyn Error>> {
     let file = File::create("tests/out/csv_c.var")?;
@@ -122,4 +191,268 @@ fn test_writer_mixed_uncompressed() -> Result<(), Box<dyn Error>> {
         std::io::copy(&mut input_file, &mut var_file)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_writer_checksums() -> Result<(), Box<dyn Error>> {
+    let mut input = vec![];
+    File::open("tests/files/sales_records.csv")?.read_to_end(&mut input)?;
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        let mut var_file = writer.write_file("sales_records.csv", Compression::default())?;
+        var_file.write_all(&input)?;
+    }
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+    let file_info = archive.get_file_info("sales_records.csv")?;
+    let raw_checksum: [u8; 32] = Blake2s::digest(&input).into();
+    assert_eq!(file_info.raw_checksum, raw_checksum);
+    assert_eq!(file_info.raw_size, input.len() as u64);
+    Ok(())
+}
+
+#[test]
+fn test_writer_unsupported_compression() -> Result<(), Box<dyn Error>> {
+    let mut writer = VarWriter::new(Cursor::new(vec![]))?;
+    assert!(matches!(writer.write_file("bsd.md", Compression::Unknown), Err(VarError::UnsupportedCompression(Compression::Unknown))));
+    let options = VarFileOptions::new(Compression::Unknown).with_frame_size(1024);
+    assert!(matches!(writer.write_file_with_options("bsd.md", options), Err(VarError::UnsupportedCompression(Compression::Unknown))));
+    Ok(())
+}
+
+#[test]
+fn test_writer_staged_parallel() -> Result<(), Box<dyn Error>> {
+    let names = ["sales_records.csv", "bsd.md"];
+    let options = VarFileOptions::new(Compression::ZStandard).with_workers(1);
+    // Write the files sequentially, for comparison
+    let mut sequential_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut sequential_bytes)?;
+        for name in names.iter() {
+            let mut var_file = writer.write_file_with_options(name, options.clone())?;
+            var_file.write_all(&std::fs::read(format!("tests/files/{}", name))?)?;
+        }
+    }
+    // Stage the same files on separate threads
+    let mut staged_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut staged_bytes)?;
+        let staged_files = std::thread::scope(|scope| {
+            let handles: Vec<_> = names.iter()
+                .map(|name| {
+                    let writer = &writer;
+                    let options = options.clone();
+                    scope.spawn(move || {
+                        let mut staged_file = writer.stage_file(name, options).unwrap();
+                        staged_file.write_all(&std::fs::read(format!("tests/files/{}", name)).unwrap()).unwrap();
+                        staged_file
+                    })
+                })
+                .collect();
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        for staged_file in staged_files {
+            writer.commit(staged_file)?;
+        }
+    }
+    let mut sequential = VarArchive::new(Cursor::new(sequential_bytes.into_inner()))?;
+    let mut staged = VarArchive::new(Cursor::new(staged_bytes.into_inner()))?;
+    for name in names.iter() {
+        let sequential_info = sequential.get_file_info(name)?;
+        let staged_info = staged.get_file_info(name)?;
+        assert_eq!(staged_info.data_checksum, sequential_info.data_checksum);
+        assert_eq!(staged_info.raw_checksum, sequential_info.raw_checksum);
+        assert_eq!(staged_info.data_size, sequential_info.data_size);
+        assert_eq!(staged_info.raw_size, sequential_info.raw_size);
+        assert_eq!(staged_info.compression_params, sequential_info.compression_params);
+        let mut file_content = vec![];
+        staged.get_file(name)?.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read(format!("tests/files/{}", name))?);
+    }
+    assert!(staged.verify()?.is_ok());
+    Ok(())
+}
+
+/// A sink that fails for good once a given number of bytes would be exceeded
+struct FailingSink {
+    inner: Cursor<Vec<u8>>,
+    limit: u64,
+    failed: bool
+}
+
+impl FailingSink {
+    fn new(limit: u64) -> Self {
+        Self {
+            inner: Cursor::new(vec![]),
+            limit,
+            failed: false
+        }
+    }
+}
+
+impl Write for FailingSink {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        if self.failed || self.inner.position() + buf.len() as u64 > self.limit {
+            self.failed = true;
+            return Err(IoError::other("Disk full"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+impl Seek for FailingSink {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_writer_finish() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/bsd.md")?;
+    let mut archive_bytes = Cursor::new(vec![]);
+    let mut writer = VarWriter::new(&mut archive_bytes)?;
+    let mut var_file = writer.write_file("bsd.md", Compression::default())?;
+    var_file.write_all(&input)?;
+    let file_info = var_file.finish()?;
+    assert_eq!(file_info.filename, "bsd.md");
+    assert_eq!(file_info.raw_size, input.len() as u64);
+    let directory_range = writer.finish()?;
+    // Only the trailer follows the directory
+    assert_eq!(directory_range.end + TRAILER_SIZE, archive_bytes.get_ref().len() as u64);
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+    assert_eq!(archive.get_file_info("bsd.md")?.data_checksum, file_info.data_checksum);
+    Ok(())
+}
+
+#[test]
+fn test_writer_finish_failing_sink() -> Result<(), Box<dyn Error>> {
+    let input = std::fs::read("tests/files/bsd.md")?;
+    let sink = FailingSink::new(256);
+    let mut writer = VarWriter::new(sink)?;
+    let mut var_file = writer.write_file("bsd.md", Compression::None)?;
+    // The data is buffered until the file is finished
+    var_file.write_all(&input)?;
+    assert!(var_file.finish().is_err());
+    assert!(writer.finish().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_writer_drop_failing_sink() -> Result<(), Box<dyn Error>> {
+    let sink = FailingSink::new(256);
+    let mut writer = VarWriter::new(sink)?;
+    {
+        let mut var_file = writer.write_file("bsd.md", Compression::default())?;
+        let _ = var_file.write_all(&std::fs::read("tests/files/bsd.md")?);
+    }
+    // Dropping must not panic, even though neither the header nor the directory fit
+    drop(writer);
+    Ok(())
+}
+
+/// A sink that can't seek, like a pipe or a socket
+struct StreamSink(Vec<u8>);
+
+impl Write for StreamSink {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_writer_stream_sink() -> Result<(), Box<dyn Error>> {
+    let mut sink = StreamSink(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut sink)?;
+        for (name, compression) in [("bsd.md", Compression::LZ4), ("sales_records.csv", Compression::default())].iter() {
+            let mut var_file = writer.write_file(name, compression.clone())?;
+            var_file.write_all(&std::fs::read(format!("tests/files/{}", name))?)?;
+            var_file.finish()?;
+        }
+        let mut staged_file = writer.stage_file("staged/bsd.md", VarFileOptions::new(Compression::Deflate))?;
+        staged_file.write_all(&std::fs::read("tests/files/bsd.md")?)?;
+        writer.commit(staged_file)?;
+        writer.finish()?;
+    }
+    let mut archive = VarArchive::new(Cursor::new(sink.0))?;
+    for (name, path) in [("bsd.md", "bsd.md"), ("sales_records.csv", "sales_records.csv"), ("staged/bsd.md", "bsd.md")].iter() {
+        let mut file_content = vec![];
+        archive.get_file(name)?.read_to_end(&mut file_content)?;
+        assert_eq!(file_content, std::fs::read(format!("tests/files/{}", path))?);
+    }
+    assert!(archive.verify()?.is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_writer_empty_archive() -> Result<(), Box<dyn Error>> {
+    let mut sink = StreamSink(vec![]);
+    VarWriter::new(&mut sink)?.finish()?;
+    let archive = VarArchive::new(Cursor::new(sink.0))?;
+    assert!(archive.get_file_list().is_empty());
+    Ok(())
+}
+
+/// Creates pseudo-random bytes, which compression can't shrink
+fn random_bytes(size: usize) -> Vec<u8> {
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_writer_spool() -> Result<(), Box<dyn Error>> {
+    let input = random_bytes(SPOOL_MEMORY_LIMIT + 1);
+    let mut spool = Spool::new();
+    spool.write_all(&input[..SPOOL_MEMORY_LIMIT])?;
+    assert!(!spool.is_file());
+    spool.write_all(&input[SPOOL_MEMORY_LIMIT..])?;
+    assert!(spool.is_file());
+    spool.seek(SeekFrom::Start(0))?;
+    let mut spooled = vec![];
+    spool.read_to_end(&mut spooled)?;
+    assert_eq!(spooled, input);
+    Ok(())
+}
+
+#[test]
+fn test_writer_large_entry() -> Result<(), Box<dyn Error>> {
+    // Larger than any buffer, so the stored data has to be spooled to disk
+    let input = random_bytes(SPOOL_MEMORY_LIMIT + 4096);
+    let mut archive_bytes = Cursor::new(vec![]);
+    {
+        let mut writer = VarWriter::new(&mut archive_bytes)?;
+        writer.write_file("stored.bin", Compression::None)?
+            .write_all(&input)?;
+        writer.write_file("compressed.bin", Compression::ZStandard)?
+            .write_all(&input)?;
+        let mut staged_file = writer.stage_file("staged.bin", VarFileOptions::new(Compression::LZ4))?;
+        staged_file.write_all(&input)?;
+        writer.commit(staged_file)?;
+        writer.finish()?;
+    }
+    let mut archive = VarArchive::new(Cursor::new(archive_bytes.into_inner()))?;
+    for name in ["stored.bin", "compressed.bin", "staged.bin"] {
+        let mut file_content = vec![];
+        archive.get_file(name)?.read_to_end(&mut file_content)?;
+        assert!(file_content == input, "{} doesn't match", name);
+    }
+    assert!(archive.verify()?.is_ok());
+    Ok(())
+}